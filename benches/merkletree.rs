@@ -0,0 +1,37 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use merkletree::hash::sha512::SHA512;
+use merkletree::merkle::tree::MerkleTree;
+use std::hint::black_box;
+
+fn leaves(count: usize) -> Vec<String> {
+    (0..count).map(|i| format!("Transaction {}", i)).collect()
+}
+
+fn bench_sha512(c: &mut Criterion) {
+    let data = vec![0xabu8; 4096];
+    c.bench_function("sha512_4k", |b| b.iter(|| SHA512::hash(black_box(&data))));
+}
+
+fn bench_build(c: &mut Criterion) {
+    let mut group = c.benchmark_group("build");
+
+    for count in [16, 256, 4096] {
+        let data = leaves(count);
+        group.bench_with_input(BenchmarkId::from_parameter(count), &data, |b, data| {
+            b.iter(|| MerkleTree::new(black_box(data.clone())))
+        });
+    }
+
+    group.finish();
+}
+
+fn bench_proofs(c: &mut Criterion) {
+    let tree = MerkleTree::new(leaves(4096));
+    let proof = tree.get_proof(1234).unwrap();
+
+    c.bench_function("get_proof_4096", |b| b.iter(|| tree.get_proof(black_box(1234))));
+    c.bench_function("verify_proof_4096", |b| b.iter(|| tree.verify_proof(black_box(&proof))));
+}
+
+criterion_group!(benches, bench_sha512, bench_build, bench_proofs);
+criterion_main!(benches);
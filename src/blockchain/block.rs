@@ -1,27 +1,57 @@
+use crate::blockchain::error::ChainError;
 use crate::hash::sha512::SHA512;
+use crate::merkle::builder::MerkleTreeBuilder;
+use crate::merkle::mode::HashMode;
+use crate::merkle::tree::{short_hash, MerkleProof, MerkleTree, OddNodePolicy, DEFAULT_ARITY};
+use serde::{Deserialize, Serialize};
 use std::time::{SystemTime, UNIX_EPOCH};
 
-#{derive(Debug, Clone)}
+#[derive(Debug, Clone)]
 pub struct Block {
     pub index: u64,
     pub timestamp: u64,
-    pub data: String,
+    pub entries: Vec<String>,
+    pub entry_count: u64,
+    pub merkle_root: String,
     pub previous_hash: String,
     pub hash: String,
     pub nonce: u64,
 }
 
+// Everything that goes into the block hash. A light client keeps only these
+// and checks entries against `merkle_root` with inclusion proofs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockHeader {
+    pub index: u64,
+    pub timestamp: u64,
+    pub entry_count: u64,
+    pub merkle_root: String,
+    pub previous_hash: String,
+    pub hash: String,
+    pub nonce: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EntryProof {
+    pub header: BlockHeader,
+    pub proof: MerkleProof,
+}
+
 impl Block {
-    pub fn new(index: u64, data: String, previous_hash: String) -> Self {
+    pub fn new(index: u64, entries: Vec<String>, previous_hash: String) -> Self {
         let timestamp = Self::current_timestamp();
         let nonce = 0;
+        let merkle_root = Self::merkle_root_of(&entries);
+        let entry_count = entries.len() as u64;
 
-        let hash = Self::calculate_hash(index, timestamp, &data, &previous_hash, nonce);
+        let hash = Self::calculate_hash(index, timestamp, entry_count, &merkle_root, &previous_hash, nonce);
 
         Block {
             index, 
             timestamp,
-            data,
+            entries,
+            entry_count,
+            merkle_root,
             previous_hash,
             hash,
             nonce,
@@ -29,38 +59,72 @@ impl Block {
     }
 
     pub fn genesis() -> Self {
-        Block::new(0, "Genesis Block".to_string(),  "0".to_string())
+        Block::new(0, vec!["Genesis Block".to_string()],  "0".to_string())
+    }
+
+    pub fn merkle_root_of(entries: &[String]) -> String {
+        Self::tree_of(entries.to_vec()).root
+    }
+
+    pub fn tree(&self) -> MerkleTree {
+        Self::tree_of(self.entries.clone())
     }
 
+    // Lone nodes are promoted rather than duplicated, so `[a, b, c]` and
+    // `[a, b, c, c]` have different roots (CVE-2012-2459).
+    fn tree_of(entries: Vec<String>) -> MerkleTree {
+        MerkleTreeBuilder::new()
+            .odd_node_policy(OddNodePolicy::Promote)
+            .expect("Binary trees can promote")
+            .build(entries)
+    }
+
+    pub fn header(&self) -> BlockHeader {
+        BlockHeader {
+            index: self.index,
+            timestamp: self.timestamp,
+            entry_count: self.entry_count,
+            merkle_root: self.merkle_root.clone(),
+            previous_hash: self.previous_hash.clone(),
+            hash: self.hash.clone(),
+            nonce: self.nonce,
+        }
+    }
+
+    // Only the header is hashed, so proof of work does not depend on the
+    // size of the block. Integers are hashed as u64 BE and hashes with a
+    // u64 BE length prefix, so no two headers share an encoding.
     pub fn calculate_hash(
         index: u64,
         timestamp: u64,
-        data: &str,
+        entry_count: u64,
+        merkle_root: &str,
         previous_hash: &str,
         nonce: u64,
     ) -> String {
-        let block_content = format!(
-        "{}{}{}{}{}",
-        index, timestamp, data, previous_hash, nonce
-    );
+        let mut block_content = Vec::new();
+        block_content.extend_from_slice(&index.to_be_bytes());
+        block_content.extend_from_slice(&timestamp.to_be_bytes());
+        block_content.extend_from_slice(&entry_count.to_be_bytes());
+        for hash in [merkle_root, previous_hash] {
+            block_content.extend_from_slice(&(hash.len() as u64).to_be_bytes());
+            block_content.extend_from_slice(hash.as_bytes());
+        }
+        block_content.extend_from_slice(&nonce.to_be_bytes());
 
-        let hash_bytes = SHA512::hash(block_content.as_bytes());
+        let hash_bytes = SHA512::hash(&block_content);
 
         Self::bytes_to_hex(&hash_bytes)
     }
     pub fn verify_hash(&self) -> bool {
-        let calculated = Self::calculate_hash(
-            self.index,
-            self.timestamp,
-            &self.data,
-            &self.previous_hash,
-            &self.nonce,
-        );
+        self.header().verify_hash()
+    }
 
-        calculated == self.hash
+    pub fn verify_merkle_root(&self) -> bool {
+        self.entries.len() as u64 == self.entry_count && Self::merkle_root_of(&self.entries) == self.merkle_root
     }
     
-    pub fn mine(&mut self, diffculty: usize) {
+    pub fn mine(&mut self, difficulty: usize) {
         let target = "0".repeat(difficulty);
         
         while !self.hash.starts_with(&target) {
@@ -68,17 +132,12 @@ impl Block {
             self.hash = Self::calculate_hash(
                 self.index,
                 self.timestamp,
-                &self.data,
+                self.entry_count,
+                &self.merkle_root,
                 &self.previous_hash,
                 self.nonce,
             );
         }
-
-        println!(
-        "Block mined! Nonce: {}, Hash: {}",
-        self.nonce,
-        &self.hash[..20]
-        );
     }
 
     fn current_timestamp() -> u64 {
@@ -98,17 +157,61 @@ impl std::fmt::Display for Block {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
         f,
-        "Block #{}|n Timestamp: {}\n Data: {}\n Previous hash: {}...\n Hash: {}...\n Nonce: {}",
+        "Block #{}|n Timestamp: {}\n Entries: {}\n Merkle root: {}...\n Previous hash: {}...\n Hash: {}...\n Nonce: {}",
         self.index,
         self.timestamp,
-        self.data,
-        &self.previous_hash[..16],
-        &self.hash[..16],
+        self.entries.len(),
+        short_hash(&self.merkle_root, 16),
+        short_hash(&self.previous_hash, 16),
+        short_hash(&self.hash, 16),
         self.nonce
         )
     }
 }
 
+impl BlockHeader {
+    pub fn calculate_hash(&self) -> String {
+        Block::calculate_hash(
+            self.index,
+            self.timestamp,
+            self.entry_count,
+            &self.merkle_root,
+            &self.previous_hash,
+            self.nonce,
+        )
+    }
+
+    pub fn verify_hash(&self) -> bool {
+        self.calculate_hash() == self.hash
+    }
+}
+
+impl EntryProof {
+    // Checks that the header is internally consistent and that `entry` is
+    // committed to by its Merkle root at the proof's index. Whether the header
+    // belongs to the right chain is up to the caller, e.g. by comparing
+    // `header.hash`.
+    pub fn verify(&self, entry: &str) -> Result<(), ChainError> {
+        let expected = self.header.calculate_hash();
+        if expected != self.header.hash {
+            return Err(ChainError::InvalidHash {
+                index: self.header.index,
+                expected,
+                actual: self.header.hash.clone(),
+            });
+        }
+
+        let entry_count = self.header.entry_count as usize;
+
+        self.proof
+            .check_shape(DEFAULT_ARITY, HashMode::Sha512, None)
+            .and_then(|_| self.proof.check_promoted_path(entry_count))
+            .and_then(|_| self.proof.check_leaf(Some(MerkleTree::hash_leaf(entry.as_bytes())), entry_count))
+            .and_then(|_| self.proof.verify(&self.header.merkle_root))
+            .map_err(ChainError::InvalidProof)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -119,33 +222,34 @@ mod tests {
 
         assert_eq!(genesis.index, 0, "Genesis block should have idx 0");
         assert_eq!(genesis.previous_hash, "0", "Genesis has no previous block");
-        assert_eq!(genesis.data, "Genesis Block");
+        assert_eq!(genesis.entries, vec!["Genesis Block"]);
         assert!(genesis.verify_hash(), "Genesis hash should be valid");
     }
 
     #[test]
     fn test_block_creation() {
-        let block1 = Block::new(1, "test data".to_String(), "previous123".to_string());
+        let block = Block::new(1, vec!["test data".to_string()], "previous123".to_string());
         
         assert_eq!(block.index, 1);
-        assert_eq!(block.data, "test data");
+        assert_eq!(block.entries, vec!["test data"]);
+        assert_eq!(block.merkle_root, MerkleTree::new(vec!["test data".to_string()]).root);
         assert_eq!(block.previous_hash, "previous123");
-        assert_eq!(block.verify_hash(), "Block hash should be valid");
+        assert!(block.verify_hash(), "Block hash should be valid");
         assert_eq!(block.hash.len(), 128, "SHA-512 hash should be 128 hex chars");
     }
 
     #[test]
     fn test_hash_changes_with_data() {
-        let block1 = Block::new(1, "Data A".to_string(), "prev".to_string());
-        let block2 = Block::new(1, "Data B".to_string(), "prev".to_string());
+        let block1 = Block::new(1, vec!["Data A".to_string()], "prev".to_string());
+        let block2 = Block::new(1, vec!["Data B".to_string()], "prev".to_string());
 
         assert_ne!(block1.hash, block2.hash, "Different data should produce different hashes");     
     }
     
     #[test]
     fn test_hash_changes_with_previous_hash() {
-        let block1 = Block::new(1, "Same Data".to_string(), "prev1".to_string());
-        let block2 = Block::new(1, "Same data".to_string(), "prev2".to_string());
+        let block1 = Block::new(1, vec!["Same Data".to_string()], "prev1".to_string());
+        let block2 = Block::new(1, vec!["Same data".to_string()], "prev2".to_string());
         
         assert_ne!(
         block1.hash, block2.hash,
@@ -155,34 +259,52 @@ mod tests {
 
     #[test]
     fn test_hash_verification() {
-        let mut block = Block::new(1, "Test".to_string(), "prev".to_string());
+        let mut block = Block::new(1, vec!["Test".to_string()], "prev".to_string());
 
         assert!(block.verify_hash(), "Original hash should be valid");
+        assert!(block.verify_merkle_root());
 
-        block.data = "Tampered".to_string();
+        block.entries[0] = "Tampered".to_string();
 
-        assert!(!block.verify_hash(), "Tampered block should fail verification");
+        assert!(!block.verify_merkle_root(), "Tampered entries should no longer match the Merkle root");
+
+        block.merkle_root = Block::merkle_root_of(&block.entries);
+
+        assert!(!block.verify_hash(), "Tampered header should fail verification");
     }
 
     #[test]
     fn test_deterministic_hashing() {
-        let block1 = Block::new(1, "Same".to_string(), "Same".to_String());
+        let block1 = Block::new(1, vec!["Same".to_string()], "Same".to_string());
 
         std::thread::sleep(std::time::Duration::from_millis(10));
 
-        let block2 = Block::new(1, "same".to_string(), "same".to_string());
+        let block2 = Block::new(1, vec!["same".to_string()], "same".to_string());
 
         assert_ne!(block1.hash, block2.hash);
 
-        let hash1 = Block::calculate_hash(1, 12345, "data", "prev", 0);
-        let hash2 = Block::calculate_hash(1, 12345, "data", "prev", 0);
+        let hash1 = Block::calculate_hash(1, 12345, 1, "data", "prev", 0);
+        let hash2 = Block::calculate_hash(1, 12345, 1, "data", "prev", 0);
 
         assert_eq!(hash1, hash2, "Same inputs should produce same hash");
+        assert_ne!(hash1, Block::calculate_hash(1, 12345, 2, "data", "prev", 0));
+    }
+
+    #[test]
+    fn test_header_fields_do_not_run_together() {
+        assert_ne!(
+            Block::calculate_hash(1, 1700000001, 4, "root", "prev", 0),
+            Block::calculate_hash(1, 170000000, 14, "root", "prev", 0)
+        );
+        assert_ne!(
+            Block::calculate_hash(1, 2, 3, "ab", "cd", 0),
+            Block::calculate_hash(1, 2, 3, "a", "bcd", 0)
+        );
     }
 
     #[test]
     fn test_mining_with_proof_of_word() {
-        let mut block = Block::new(1, "Mine me".to_string(), "prev".to_string());
+        let mut block = Block::new(1, vec!["Mine me".to_string()], "prev".to_string());
 
         block.mine(2);
 
@@ -195,10 +317,10 @@ mod tests {
     
     #[test]
     fn test_block_display() {
-        let block = Block::new(1, "Display test".to_string(), "prev".sto_string());
+        let block = Block::new(1, vec!["Display test".to_string()], "prev".to_string());
         let display = format!("{}", block);
 
         assert!(display.contains("Block #1"));
-        assert!(display.contains("Display test"));
+        assert!(display.contains("Entries: 1"));
     }
 }
@@ -1,4 +1,6 @@
-use crate::blockchain::block::Block;
+use crate::blockchain::block::{Block, EntryProof};
+use crate::blockchain::error::ChainError;
+use crate::merkle::tree::short_hash;
 
 #[derive(Debug, Clone)]
 pub struct Blockchain {
@@ -6,8 +8,14 @@ pub struct Blockchain {
     pub difficulty: usize,
 }
 
+impl Default for Blockchain {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Blockchain {
-    pub fn new() -> {
+    pub fn new() -> Self {
         let genesis = Block::genesis();
         Blockchain {
             blocks: vec![genesis],
@@ -27,11 +35,11 @@ impl Blockchain {
         self.blocks.last().expect("Blockchain should never be empty")
     }
 
-    pub fn  add_block(&mut self, data: String, mine: bool) {
+    pub fn  add_block(&mut self, entries: Vec<String>, mine: bool) {
         let previous_block = self.latest_block();
         let mut new_block = Block::new(
             self.blocks.len() as u64,
-            data,
+            entries,
             previous_block.hash.clone(),
         );
 
@@ -42,189 +50,98 @@ impl Blockchain {
     }
     
     pub fn is_valid(&self) -> bool {
-        if self.blocks.is_empty() {
-            return false;
-        }
-        let genesis = &self.blocks[0];
+        self.validate().is_ok()
+    }
+
+    // Reports the first block that breaks the chain.
+    pub fn validate(&self) -> Result<(), ChainError> {
+        let genesis = self.blocks.first().ok_or(ChainError::EmptyChain)?;
         if genesis.index != 0 || genesis.previous_hash != "0" {
-            println!("Invalid genesis block");
-            return false;
-        }
-        if !genesis.verify_hash() {
-            println!("Genesis block hash is invalid");
-            return false;
+            return Err(ChainError::InvalidGenesis {
+                index: genesis.index,
+                previous_hash: genesis.previous_hash.clone(),
+            });
         }
+        Self::check_hash(genesis)?;
+
         for i in 1..self.blocks.len() {
             let current = &self.blocks[i];
             let previous = &self.blocks[i - 1];
 
-            if !current.verify_hash() {
-                println!("Block {} has invalid hash", i);
-                return false;
-            }
+            Self::check_hash(current)?;
 
             if current.previous_hash != previous.hash {
-                println!("Block {} does not link to previous block correctly\nExpected: {}\nGot: {}",
-                i,previous.hash, current.previous_hash);
-                return false;
+                return Err(ChainError::BrokenLink {
+                    index: current.index,
+                    expected: previous.hash.clone(),
+                    actual: current.previous_hash.clone(),
+                });
             }
             if current.index != previous.index + 1 {
-                println!("Block {} has incorrect index", i );
-                return false;
+                return Err(ChainError::IndexMismatch {
+                    expected: previous.index + 1,
+                    actual: current.index,
+                });
             }
         }
-        true
-    }
-}
-pub fn tamper_block(&mut self, index: usize, new_data: String) -> bool {
-    if index >= self.blocks.len() {
-        return false;
-    }
-    println!("\n  TAMPERING WITH BLOCK {}...", index);
-    self.blocks[index].data = new_data;
-    println!("Blockchain is now: {}", if self.is_valid() { "VALID" } else { "INVALID "});
-    true
-}
-
-pub fn stats(&self) -> BlockchainStats {
-    BlockchainStats {
-        total_blocks: self.blocks.len(),
-        is_valid: self.is_valid(),
-        difficulty: self.difficulty,
-        genesis_hash: self.blocks[0].hash.clone(),
-        latest_hash: self.latest_block().hash.clone(),
-    }
-}
-    
-
-pub fn display(&self) {
-    println!("\n{}", "=".repeat(50));
-    println!(" BLOCKCHAIN");
-    println!("{}", "=".repeat(50));
-    println!("Total Blocks: {}", self.blocks.len());
-    println!("Difficulty: {}", self.difficulty);
-    println!("Valid: {}\n", if self.is_valid() { "✓" } else { "✗" });
-
-    for block in &self.blocks {
-        println!("{}", block);
-        println!("{}", "-".repeat(50));
-    }
-
-}
-
-
-#[derive(Debug)]
-pub struct BlockchainStats {
-    pub total_blocks: usize,
-    pub is_valid: bool,
-    pub difficulty: usize,
-    pub genesis_hash: String,
-    pub latest_hash: String,
-}
-
-impl std::fmt::Display for BlockchainStats {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "Blockchain Stats:\n  Blocks: {}\n  Valid: {}\n  Difficulty: {}\n  Genesis: {}...\n  Latest: {}...",
-            self.total_blocks,
-            self.difficulty,
-            &self.genesis_hash[..16],
-            &self.latest_hash[..16]
-        )
+        Ok(())
     }
-}
-
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_blockchain_creation() {
-        let blockchain = Blockchain::new();
-        
-        assert_eq!(blockchain.blocks.len(), 1, "Should start with genesis block");
-        assert!(blockchain.is_valid(), "New blockchain should be valid");
-    }
+    fn check_hash(block: &Block) -> Result<(), ChainError> {
+        if !block.verify_hash() {
+            return Err(ChainError::InvalidHash {
+                index: block.index,
+                expected: block.header().calculate_hash(),
+                actual: block.hash.clone(),
+            });
+        }
 
-    #[test]
-    fn test_add_block() {
-        let mut blockchain = Blockchain::new();
-        
-        blockchain.add_block("First block".to_string(), false);
-        blockchain.add_block("Second block".to_string(), false);
-        
-        assert_eq!(blockchain.blocks.len(), 3, "Should have 3 blocks total");
-        assert!(blockchain.is_valid(), "Blockchain should remain valid");
-    }
+        if block.entries.len() as u64 != block.entry_count {
+            return Err(ChainError::EntryCountMismatch {
+                index: block.index,
+                expected: block.entry_count,
+                actual: block.entries.len(),
+            });
+        }
 
-    #[test]
-    fn test_block_linking() {
-        let mut blockchain = Blockchain::new();
-        
-        blockchain.add_block("Block 1".to_string(), false);
-        blockchain.add_block("Block 2".to_string(), false);
-        
-        // Verify each block links to the previous
-        for i in 1..blockchain.blocks.len() {
-            let current = &blockchain.blocks[i];
-            let previous = &blockchain.blocks[i - 1];
-            
-            assert_eq!(
-                current.previous_hash, previous.hash,
-                "Block {} should link to block {}", i, i - 1
-            );
+        let expected = Block::merkle_root_of(&block.entries);
+        if expected != block.merkle_root {
+            return Err(ChainError::MerkleRootMismatch {
+                index: block.index,
+                expected,
+                actual: block.merkle_root.clone(),
+            });
         }
+        Ok(())
     }
 
-    #[test]
-    fn test_tampering_detection() {
-        let mut blockchain = Blockchain::new();
-        
-        blockchain.add_block("Block 1".to_string(), false);
-        blockchain.add_block("Block 2".to_string(), false);
-        
-        assert!(blockchain.is_valid(), "Original chain should be valid");
-        
-        // Tamper with block 1's data
-        blockchain.blocks[1].data = "TAMPERED DATA".to_string();
-        
-        assert!(!blockchain.is_valid(), "Tampered chain should be invalid");
-    }
+    // The proof carries the block header, so a client that only follows
+    // headers can check the entry without downloading the block.
+    pub fn prove_entry(&self, block_index: usize, entry_index: usize) -> Result<EntryProof, ChainError> {
+        let block = self.blocks.get(block_index).ok_or(ChainError::BlockNotFound {
+            index: block_index,
+            len: self.blocks.len(),
+        })?;
 
-    #[test]
-    fn test_hash_chain_immutability() {
-        let mut blockchain = Blockchain::new();
-        
-        blockchain.add_block("Block 1".to_string(), false);
-        blockchain.add_block("Block 2".to_string(), false);
-        blockchain.add_block("Block 3".to_string(), false);
-        
-        let original_hash_2 = blockchain.blocks[2].hash.clone();
-        let original_hash_3 = blockchain.blocks[3].hash.clone();
-        
-
-        blockchain.blocks[1].data = "MODIFIED".to_string();
-        
-
-        assert_eq!(blockchain.blocks[2].hash, original_hash_2);
-        assert_eq!(blockchain.blocks[3].hash, original_hash_3);
+        let proof = block.tree().get_proof(entry_index).map_err(|_| ChainError::EntryNotFound {
+            block: block.index,
+            index: entry_index,
+            len: block.entries.len(),
+        })?;
 
-        assert!(!blockchain.is_valid());
+        Ok(EntryProof {
+            header: block.header(),
+            proof,
+        })
     }
 
-    #[test]       pub fn tamper_block(&mut self, index: usize, new_data: String) -> bool {
+    pub fn tamper_block(&mut self, index: usize, new_data: String) -> Result<(), ChainError> {
         if index >= self.blocks.len() {
-            return false;
+            return Err(ChainError::BlockNotFound { index, len: self.blocks.len() });
         }
-        
-        println!("\n⚠️  TAMPERING WITH BLOCK {}...", index);
-        self.blocks[index].data = new_data;
-        
-        println!("Blockchain is now: {}", if self.is_valid() { "VALID ✓" } else { "INVALID ✗" });
-        
-        true
+
+        self.blocks[index].entries = vec![new_data];
+        Ok(())
     }
     
     /// Get blockchain statistics
@@ -237,20 +154,22 @@ mod tests {
             latest_hash: self.latest_block().hash.clone(),
         }
     }
-    
-    /// Display the entire blockchain
-    pub fn display(&self) {
-        println!("\n{}", "=".repeat(50));
-        println!(" BLOCKCHAIN");
-        println!("{}", "=".repeat(50));
-        println!("Total Blocks: {}", self.blocks.len());
-        println!("Difficulty: {}", self.difficulty);
-        println!("Valid: {}\n", if self.is_valid() { "✓" } else { "✗" });
-        
+}
+
+impl std::fmt::Display for Blockchain {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}", "=".repeat(50))?;
+        writeln!(f, " BLOCKCHAIN")?;
+        writeln!(f, "{}", "=".repeat(50))?;
+        writeln!(f, "Total Blocks: {}", self.blocks.len())?;
+        writeln!(f, "Difficulty: {}", self.difficulty)?;
+        writeln!(f, "Valid: {}\n", if self.is_valid() { "✓" } else { "✗" })?;
+
         for block in &self.blocks {
-            println!("{}", block);
-            println!("{}", "-".repeat(50));
+            writeln!(f, "{}", block)?;
+            writeln!(f, "{}", "-".repeat(50))?;
         }
+        Ok(())
     }
 }
 
@@ -272,17 +191,17 @@ impl std::fmt::Display for BlockchainStats {
             self.total_blocks,
             if self.is_valid { "✓" } else { "✗" },
             self.difficulty,
-            &self.genesis_hash[..16],
-            &self.latest_hash[..16]
+            short_hash(&self.genesis_hash, 16),
+            short_hash(&self.latest_hash, 16)
         )
     }
 }
 
-
-
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::merkle::error::ProofError;
+    use crate::merkle::tree::MerkleTree;
 
     #[test]
     fn test_blockchain_creation() {
@@ -296,8 +215,8 @@ mod tests {
     fn test_add_block() {
         let mut blockchain = Blockchain::new();
         
-        blockchain.add_block("First block".to_string(), false);
-        blockchain.add_block("Second block".to_string(), false);
+        blockchain.add_block(vec!["First block".to_string()], false);
+        blockchain.add_block(vec!["Second block".to_string()], false);
         
         assert_eq!(blockchain.blocks.len(), 3, "Should have 3 blocks total");
         assert!(blockchain.is_valid(), "Blockchain should remain valid");
@@ -307,8 +226,8 @@ mod tests {
     fn test_block_linking() {
         let mut blockchain = Blockchain::new();
         
-        blockchain.add_block("Block 1".to_string(), false);
-        blockchain.add_block("Block 2".to_string(), false);
+        blockchain.add_block(vec!["Block 1".to_string()], false);
+        blockchain.add_block(vec!["Block 2".to_string()], false);
         
         // Verify each block links to the previous
         for i in 1..blockchain.blocks.len() {
@@ -326,13 +245,13 @@ mod tests {
     fn test_tampering_detection() {
         let mut blockchain = Blockchain::new();
         
-        blockchain.add_block("Block 1".to_string(), false);
-        blockchain.add_block("Block 2".to_string(), false);
+        blockchain.add_block(vec!["Block 1".to_string()], false);
+        blockchain.add_block(vec!["Block 2".to_string()], false);
         
         assert!(blockchain.is_valid(), "Original chain should be valid");
         
         // Tamper with block 1's data
-        blockchain.blocks[1].data = "TAMPERED DATA".to_string();
+        blockchain.blocks[1].entries[0] = "TAMPERED DATA".to_string();
         
         assert!(!blockchain.is_valid(), "Tampered chain should be invalid");
     }
@@ -341,15 +260,15 @@ mod tests {
     fn test_hash_chain_immutability() {
         let mut blockchain = Blockchain::new();
         
-        blockchain.add_block("Block 1".to_string(), false);
-        blockchain.add_block("Block 2".to_string(), false);
-        blockchain.add_block("Block 3".to_string(), false);
+        blockchain.add_block(vec!["Block 1".to_string()], false);
+        blockchain.add_block(vec!["Block 2".to_string()], false);
+        blockchain.add_block(vec!["Block 3".to_string()], false);
         
         let original_hash_2 = blockchain.blocks[2].hash.clone();
         let original_hash_3 = blockchain.blocks[3].hash.clone();
         
         // Tamper with block 1
-        blockchain.blocks[1].data = "MODIFIED".to_string();
+        blockchain.blocks[1].entries[0] = "MODIFIED".to_string();
         
         // Block 2 and 3's stored hashes don't change (they're fixed values)
         // But their previous_hash references are now wrong
@@ -374,7 +293,7 @@ mod tests {
     fn test_mining_with_difficulty() {
         let mut blockchain = Blockchain::with_difficulty(2);
         
-        blockchain.add_block("Mined block".to_string(), true);
+        blockchain.add_block(vec!["Mined block".to_string()], true);
         
         let latest = blockchain.latest_block();
         assert!(
@@ -390,7 +309,7 @@ mod tests {
         let mut blockchain = Blockchain::new();
         
         for i in 1..=5 {
-            blockchain.add_block(format!("Block {}", i), false);
+            blockchain.add_block(vec![format!("Block {}", i)], false);
         }
         
         for (i, block) in blockchain.blocks.iter().enumerate() {
@@ -402,83 +321,196 @@ mod tests {
     fn test_latest_block() {
         let mut blockchain = Blockchain::new();
         
-        blockchain.add_block("Block 1".to_string(), false);
+        blockchain.add_block(vec!["Block 1".to_string()], false);
         let latest = blockchain.latest_block();
-        assert_eq!(latest.data, "Block 1");
+        assert_eq!(latest.entries, vec!["Block 1"]);
         
-        blockchain.add_block("Block 2".to_string(), false);
+        blockchain.add_block(vec!["Block 2".to_string()], false);
         let latest = blockchain.latest_block();
-        assert_eq!(latest.data, "Block 2");
+        assert_eq!(latest.entries, vec!["Block 2"]);
     }
 
     #[test]
     fn test_stats() {
         let mut blockchain = Blockchain::new();
-        blockchain.add_block("Test".to_string(), false);
+        blockchain.add_block(vec!["Test".to_string()], false);
         
         let stats = blockchain.stats();
         assert_eq!(stats.total_blocks, 2);
         assert!(stats.is_valid);
         assert_eq!(stats.difficulty, 2);
     }
-} 
-    fn test_genesis_validation() {
-        let blockchain = Blockchain::new();
-        
-        assert_eq!(blockchain.blocks[0].index, 0);
-        assert_eq!(blockchain.blocks[0].previous_hash, "0");
-        assert!(blockchain.is_valid());
+
+    #[test]
+    fn test_display() {
+        let mut blockchain = Blockchain::new();
+        blockchain.add_block(vec!["Test".to_string()], false);
+
+        let text = blockchain.to_string();
+        assert!(text.contains("Total Blocks: 2"));
+        assert!(text.contains(short_hash(&blockchain.latest_block().hash, 16)));
+        assert!(blockchain.stats().to_string().contains("Blocks: 2"));
     }
 
     #[test]
-    fn test_mining_with_difficulty() {
-        let mut blockchain = Blockchain::with_difficulty(2);
-        
-        blockchain.add_block("Mined block".to_string(), true);
-        
-        let latest = blockchain.latest_block();
-        assert!(
-            latest.hash.starts_with("00"),
-            "Mined block should have hash starting with '00'"
+    fn test_validate_reports_tampered_block() {
+        let mut blockchain = Blockchain::new();
+        blockchain.add_block(vec!["Block 1".to_string()], false);
+        blockchain.add_block(vec!["Block 2".to_string()], false);
+        assert_eq!(blockchain.validate(), Ok(()));
+
+        let stored = blockchain.blocks[1].merkle_root.clone();
+        blockchain.tamper_block(1, "TAMPERED".to_string()).unwrap();
+
+        match blockchain.validate() {
+            Err(ChainError::MerkleRootMismatch { index, expected, actual }) => {
+                assert_eq!(index, 1);
+                assert_eq!(actual, stored);
+                assert_ne!(expected, stored);
+            }
+            other => panic!("Expected a Merkle root mismatch, got {:?}", other),
+        }
+
+        assert_eq!(
+            blockchain.tamper_block(5, "missing".to_string()),
+            Err(ChainError::BlockNotFound { index: 5, len: 3 })
         );
-        assert!(latest.nonce > 0, "Mining should find a nonce");
-        assert!(blockchain.is_valid(), "Mined blockchain should be valid");
     }
 
     #[test]
-    fn test_sequential_indices() {
+    fn test_validate_reports_broken_link() {
         let mut blockchain = Blockchain::new();
-        
-        for i in 1..=5 {
-            blockchain.add_block(format!("Block {}", i), false);
+        blockchain.add_block(vec!["Block 1".to_string()], false);
+        blockchain.add_block(vec!["Block 2".to_string()], false);
+
+        let genesis_hash = blockchain.blocks[0].hash.clone();
+        let relinked = Block::new(2, vec!["Block 2".to_string()], genesis_hash.clone());
+        blockchain.blocks[2] = relinked;
+
+        assert_eq!(
+            blockchain.validate(),
+            Err(ChainError::BrokenLink {
+                index: 2,
+                expected: blockchain.blocks[1].hash.clone(),
+                actual: genesis_hash,
+            })
+        );
+
+        blockchain.blocks.clear();
+        assert_eq!(blockchain.validate(), Err(ChainError::EmptyChain));
+        assert!(!blockchain.is_valid());
+    }
+
+    #[test]
+    fn test_prove_entry_with_header() {
+        let mut blockchain = Blockchain::new();
+        let entries: Vec<String> = (0..5).map(|i| format!("Alice pays Bob {}", i)).collect();
+        blockchain.add_block(entries.clone(), true);
+
+        let block = &blockchain.blocks[1];
+        assert!(block.hash.starts_with("00"));
+        assert_eq!(block.merkle_root, Block::merkle_root_of(&entries));
+
+        for (i, entry) in entries.iter().enumerate() {
+            let proof = blockchain.prove_entry(1, i).unwrap();
+
+            assert_eq!(proof.header, block.header());
+            assert_eq!(proof.verify(entry), Ok(()));
         }
-        
-        for (i, block) in blockchain.blocks.iter().enumerate() {
-            assert_eq!(block.index, i as u64, "Block index should match position");
+
+        let proof = blockchain.prove_entry(1, 2).unwrap();
+        assert!(matches!(
+            proof.verify("Alice pays Bob 9"),
+            Err(ChainError::InvalidProof(ProofError::LeafMismatch { .. }))
+        ));
+
+        let mut forged = proof.clone();
+        forged.header.merkle_root = Block::merkle_root_of(&["Alice pays Bob 9".to_string()]);
+        assert!(matches!(forged.verify("Alice pays Bob 2"), Err(ChainError::InvalidHash { index: 1, .. })));
+
+        assert_eq!(
+            blockchain.prove_entry(1, 5).map(|_| ()),
+            Err(ChainError::EntryNotFound { block: 1, index: 5, len: 5 })
+        );
+        assert_eq!(
+            blockchain.prove_entry(2, 0).map(|_| ()),
+            Err(ChainError::BlockNotFound { index: 2, len: 2 })
+        );
+    }
+
+    #[test]
+    fn test_validate_reports_tampered_entries() {
+        let mut blockchain = Blockchain::new();
+        blockchain.add_block(vec!["Entry A".to_string(), "Entry B".to_string()], false);
+
+        let root = blockchain.blocks[1].merkle_root.clone();
+        blockchain.blocks[1].entries[1] = "Entry C".to_string();
+
+        // The header hash still matches, only the entries no longer do.
+        assert!(blockchain.blocks[1].verify_hash());
+        match blockchain.validate() {
+            Err(ChainError::MerkleRootMismatch { index, actual, .. }) => {
+                assert_eq!(index, 1);
+                assert_eq!(actual, root);
+            }
+            other => panic!("Expected a Merkle root mismatch, got {:?}", other),
         }
+
+        blockchain.blocks[1].entries[1] = "Entry B".to_string();
+        blockchain.blocks[1].entries.push("Entry C".to_string());
+        assert_eq!(
+            blockchain.validate(),
+            Err(ChainError::EntryCountMismatch { index: 1, expected: 2, actual: 3 })
+        );
     }
 
     #[test]
-    fn test_latest_block() {
+    fn test_duplicated_last_entry_rejected() {
+        let entries: Vec<String> = ["a", "b", "c"].iter().map(|entry| entry.to_string()).collect();
+        let mut duplicated = entries.clone();
+        duplicated.push("c".to_string());
+        assert_ne!(Block::merkle_root_of(&entries), Block::merkle_root_of(&duplicated));
+
         let mut blockchain = Blockchain::new();
-        
-        blockchain.add_block("Block 1".to_string(), false);
-        let latest = blockchain.latest_block();
-        assert_eq!(latest.data, "Block 1");
-        
-        blockchain.add_block("Block 2".to_string(), false);
-        let latest = blockchain.latest_block();
-        assert_eq!(latest.data, "Block 2");
+        blockchain.add_block(entries, false);
+
+        let mut padded = blockchain.clone();
+        padded.blocks[1].entries.push("c".to_string());
+        assert_eq!(
+            padded.validate(),
+            Err(ChainError::EntryCountMismatch { index: 1, expected: 3, actual: 4 })
+        );
+
+        // Rewriting the count as well breaks the header hash.
+        padded.blocks[1].entry_count = 4;
+        assert!(matches!(padded.validate(), Err(ChainError::InvalidHash { index: 1, .. })));
+
+        let mut forged = blockchain.prove_entry(1, 2).unwrap();
+        forged.proof.leaf_index = 3;
+        assert_eq!(
+            forged.verify("c"),
+            Err(ChainError::InvalidProof(ProofError::IndexOutOfRange { index: 3, leaf_count: 3 }))
+        );
     }
 
     #[test]
-    fn test_stats() {
+    fn test_inner_node_is_not_an_entry() {
+        let entries: Vec<String> = (0..4).map(|i| format!("Entry {}", i)).collect();
         let mut blockchain = Blockchain::new();
-        blockchain.add_block("Test".to_string(), false);
-        
-        let stats = blockchain.stats();
-        assert_eq!(stats.total_blocks, 2);
-        assert!(stats.is_valid);
-        assert_eq!(stats.difficulty, 2);
+        blockchain.add_block(entries.clone(), false);
+
+        // An "entry" spelling out two child hashes hashes to their parent.
+        let leaves: Vec<String> = entries.iter().map(|entry| MerkleTree::hash_leaf(entry.as_bytes())).collect();
+        let inner = format!("{}{}", leaves[0], leaves[1]);
+
+        let mut proof = blockchain.prove_entry(1, 0).unwrap();
+        proof.proof.proof_path.remove(0);
+        proof.proof.leaf_hash = MerkleTree::hash_leaf(inner.as_bytes());
+        assert_eq!(proof.proof.verify(&proof.header.merkle_root), Ok(()));
+
+        assert_eq!(
+            proof.verify(&inner),
+            Err(ChainError::InvalidProof(ProofError::PathMismatch { index: 0, leaf_count: 4 }))
+        );
     }
 }
@@ -0,0 +1,71 @@
+use crate::merkle::error::ProofError;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChainError {
+    EmptyChain,
+    InvalidGenesis { index: u64, previous_hash: String },
+    InvalidHash { index: u64, expected: String, actual: String },
+    BrokenLink { index: u64, expected: String, actual: String },
+    IndexMismatch { expected: u64, actual: u64 },
+    BlockNotFound { index: usize, len: usize },
+    BlockHashMismatch { index: u64, expected: String, actual: String },
+    NoPendingDigests,
+    MerkleRootMismatch { index: u64, expected: String, actual: String },
+    EntryCountMismatch { index: u64, expected: u64, actual: usize },
+    EntryNotFound { block: u64, index: usize, len: usize },
+    MissingAnchor { index: u64 },
+    InvalidProof(ProofError),
+}
+
+impl fmt::Display for ChainError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChainError::EmptyChain => write!(f, "chain has no genesis block"),
+            ChainError::InvalidGenesis { index, previous_hash } => write!(
+                f,
+                "invalid genesis block: index {}, previous hash {:?}",
+                index, previous_hash
+            ),
+            ChainError::InvalidHash { index, expected, actual } => write!(
+                f,
+                "block {} has invalid hash: expected {}, stored {}",
+                index, expected, actual
+            ),
+            ChainError::BrokenLink { index, expected, actual } => write!(
+                f,
+                "block {} does not link to its predecessor: expected {}, got {}",
+                index, expected, actual
+            ),
+            ChainError::IndexMismatch { expected, actual } => {
+                write!(f, "expected block index {}, got {}", expected, actual)
+            }
+            ChainError::BlockNotFound { index, len } => {
+                write!(f, "block {} out of range for a chain of {} blocks", index, len)
+            }
+            ChainError::BlockHashMismatch { index, expected, actual } => write!(
+                f,
+                "block {} has hash {}, receipt names {}",
+                index, expected, actual
+            ),
+            ChainError::MerkleRootMismatch { index, expected, actual } => write!(
+                f,
+                "entries of block {} hash to {}, header has {}",
+                index, expected, actual
+            ),
+            ChainError::EntryCountMismatch { index, expected, actual } => write!(
+                f,
+                "block {} commits to {} entries, holds {}",
+                index, expected, actual
+            ),
+            ChainError::EntryNotFound { block, index, len } => {
+                write!(f, "entry {} out of range for block {} with {} entries", index, block, len)
+            }
+            ChainError::NoPendingDigests => write!(f, "no document digests are waiting to be anchored"),
+            ChainError::MissingAnchor { index } => write!(f, "block {} does not anchor a Merkle root", index),
+            ChainError::InvalidProof(err) => write!(f, "invalid inclusion proof: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for ChainError {}
@@ -1,5 +1,9 @@
 pub mod block;
 pub mod chain;
+pub mod error;
+pub mod timestamp;
 
-pub use block::Block;
-pub use chain::Blockhain;
+pub use block::{Block, BlockHeader, EntryProof};
+pub use chain::Blockchain;
+pub use error::ChainError;
+pub use timestamp::{Aggregator, Receipt};
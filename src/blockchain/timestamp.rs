@@ -0,0 +1,275 @@
+use crate::blockchain::block::Block;
+use crate::blockchain::chain::Blockchain;
+use crate::blockchain::error::ChainError;
+use crate::merkle::builder::MerkleTreeBuilder;
+use crate::merkle::mode::HashMode;
+use crate::merkle::tree::{MerkleProof, MerkleTree, OddNodePolicy, DEFAULT_ARITY};
+use serde::{Deserialize, Serialize};
+
+// The entry of a block that anchors a batch of documents: this prefix
+// followed by `<document count>:<hex Merkle root>`. The count pins receipt
+// proofs to the shape of the batch tree.
+pub const ANCHOR_PREFIX: &str = "merkle-root:";
+
+pub fn anchored_root(block: &Block) -> Option<(usize, &str)> {
+    block.entries.iter().find_map(|entry| {
+        let (count, root) = entry.strip_prefix(ANCHOR_PREFIX)?.split_once(':')?;
+        let parsed: usize = count.parse().ok()?;
+
+        // Only the canonical spelling of a non-zero count is accepted.
+        (parsed > 0 && parsed.to_string() == count).then_some((parsed, root))
+    })
+}
+
+// Digests are the SHA512 leaf hashes of the documents, so a verifier only
+// needs the document bytes to recompute them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Aggregator {
+    pub pending: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Receipt {
+    pub proof: MerkleProof,
+    pub block_index: u64,
+    pub block_hash: String,
+}
+
+impl Aggregator {
+
+    pub fn new() -> Self {
+        Aggregator::default()
+    }
+
+    // Returns the position of the document in the next batch.
+    pub fn submit(&mut self, document: &[u8]) -> usize {
+        self.pending.push(MerkleTree::hash_leaf(document));
+        self.pending.len() - 1
+    }
+
+    // Commits every pending digest in one new block and returns a receipt
+    // per submission, in submission order.
+    pub fn anchor(&mut self, chain: &mut Blockchain, mine: bool) -> Result<Vec<Receipt>, ChainError> {
+        if self.pending.is_empty() {
+            return Err(ChainError::NoPendingDigests);
+        }
+
+        let tree = MerkleTreeBuilder::new()
+            .odd_node_policy(OddNodePolicy::Promote)
+            .expect("Binary trees can promote")
+            .build_from_leaf_hashes(std::mem::take(&mut self.pending));
+        chain.add_block(vec![format!("{}{}:{}", ANCHOR_PREFIX, tree.leaf_count(), tree.root)], mine);
+
+        let block = chain.latest_block();
+        let receipts = (0..tree.leaf_count())
+            .map(|i| Receipt {
+                proof: tree.get_proof(i).expect("Index is within the tree"),
+                block_index: block.index,
+                block_hash: block.hash.clone(),
+            })
+            .collect();
+
+        Ok(receipts)
+    }
+}
+
+impl Receipt {
+
+    // Checks that `document` is committed to by the root written into the
+    // receipt's block and that the chain up to the tip is intact.
+    pub fn verify(&self, chain: &Blockchain, document: &[u8]) -> Result<(), ChainError> {
+        chain.validate()?;
+
+        let block = usize::try_from(self.block_index)
+            .ok()
+            .and_then(|index| chain.blocks.get(index))
+            .ok_or(ChainError::BlockNotFound {
+                index: self.block_index as usize,
+                len: chain.blocks.len(),
+            })?;
+
+        if block.hash != self.block_hash {
+            return Err(ChainError::BlockHashMismatch {
+                index: block.index,
+                expected: block.hash.clone(),
+                actual: self.block_hash.clone(),
+            });
+        }
+
+        let (count, root) = anchored_root(block).ok_or(ChainError::MissingAnchor { index: block.index })?;
+
+        self.proof
+            .check_shape(DEFAULT_ARITY, HashMode::Sha512, None)
+            .and_then(|_| self.proof.check_promoted_path(count))
+            .and_then(|_| self.proof.check_leaf(Some(MerkleTree::hash_leaf(document)), count))
+            .and_then(|_| self.proof.verify(root))
+            .map_err(ChainError::InvalidProof)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merkle::error::ProofError;
+
+    fn documents() -> Vec<Vec<u8>> {
+        (0..5).map(|i| format!("Contract draft {}", i).into_bytes()).collect()
+    }
+
+    fn anchored_chain() -> (Blockchain, Vec<Receipt>) {
+        let mut chain = Blockchain::new();
+        chain.add_block(vec!["Unrelated".to_string()], false);
+
+        let mut aggregator = Aggregator::new();
+        for (i, document) in documents().iter().enumerate() {
+            assert_eq!(aggregator.submit(document), i);
+        }
+
+        let receipts = aggregator.anchor(&mut chain, false).unwrap();
+        assert!(aggregator.pending.is_empty());
+
+        (chain, receipts)
+    }
+
+    #[test]
+    fn test_receipts_verify_with_document_bytes() {
+        let (chain, receipts) = anchored_chain();
+        let block = chain.latest_block();
+
+        assert_eq!(receipts.len(), 5);
+        assert_eq!(anchored_root(block).map(|(count, _)| count), Some(5));
+        assert!(chain.is_valid());
+
+        for (receipt, document) in receipts.iter().zip(documents()) {
+            assert_eq!(receipt.block_index, 2);
+            assert_eq!(receipt.block_hash, block.hash);
+            assert_eq!(receipt.verify(&chain, &document), Ok(()));
+        }
+    }
+
+    #[test]
+    fn test_wrong_document_rejected() {
+        let (chain, receipts) = anchored_chain();
+
+        assert!(matches!(
+            receipts[1].verify(&chain, b"Contract draft 9"),
+            Err(ChainError::InvalidProof(ProofError::LeafMismatch { .. }))
+        ));
+        assert!(matches!(
+            receipts[1].verify(&chain, &documents()[2]),
+            Err(ChainError::InvalidProof(ProofError::LeafMismatch { .. }))
+        ));
+    }
+
+    #[test]
+    fn test_receipt_proof_shape_pinned() {
+        let (chain, receipts) = anchored_chain();
+
+        let mut wide = receipts[0].clone();
+        wide.proof.arity = 4;
+        assert_eq!(
+            wide.verify(&chain, &documents()[0]),
+            Err(ChainError::InvalidProof(ProofError::ArityMismatch { expected: 2, actual: 4 }))
+        );
+
+        let mut foreign = receipts[0].clone();
+        foreign.proof.mode = HashMode::Rfc6962;
+        assert_eq!(
+            foreign.verify(&chain, &documents()[0]),
+            Err(ChainError::InvalidProof(ProofError::HashModeMismatch {
+                expected: HashMode::Sha512,
+                actual: HashMode::Rfc6962,
+            }))
+        );
+    }
+
+    #[test]
+    fn test_inner_node_receipt_rejected() {
+        let (chain, receipts) = anchored_chain();
+        let digests: Vec<String> = documents().iter().map(|document| MerkleTree::hash_leaf(document)).collect();
+
+        // The parent of the first two digests is the SHA512 of their joined
+        // hex, so that text passes as a document one level up the tree.
+        let forged_document = format!("{}{}", digests[0], digests[1]);
+        let mut forged = receipts[0].clone();
+        forged.proof.leaf_hash = MerkleTree::hash_leaf(forged_document.as_bytes());
+        forged.proof.proof_path.remove(0);
+        assert_eq!(forged.proof.compute_root().as_deref(), Ok(anchored_root(chain.latest_block()).unwrap().1));
+
+        assert_eq!(
+            forged.verify(&chain, forged_document.as_bytes()),
+            Err(ChainError::InvalidProof(ProofError::PathMismatch { index: 0, leaf_count: 5 }))
+        );
+
+        let mut overflow = receipts[4].clone();
+        overflow.proof.leaf_index = usize::MAX;
+        assert_eq!(
+            overflow.verify(&chain, &documents()[4]),
+            Err(ChainError::InvalidProof(ProofError::IndexOutOfRange { index: usize::MAX, leaf_count: 5 }))
+        );
+    }
+
+    #[test]
+    fn test_anchor_entry_parsing() {
+        let mut block = Block::new(1, vec![format!("{}3:{}", ANCHOR_PREFIX, "ab")], "prev".to_string());
+        assert_eq!(anchored_root(&block), Some((3, "ab")));
+
+        for entry in ["merkle-root:ab", "merkle-root:0:ab", "merkle-root:03:ab", "merkle-root:+3:ab"] {
+            block.entries = vec![entry.to_string()];
+            assert_eq!(anchored_root(&block), None, "{}", entry);
+        }
+    }
+
+    #[test]
+    fn test_receipt_bound_to_its_block() {
+        let (mut chain, receipts) = anchored_chain();
+
+        let mut elsewhere = receipts[0].clone();
+        elsewhere.block_index = 1;
+        assert_eq!(
+            elsewhere.verify(&chain, &documents()[0]),
+            Err(ChainError::BlockHashMismatch {
+                index: 1,
+                expected: chain.blocks[1].hash.clone(),
+                actual: receipts[0].block_hash.clone(),
+            })
+        );
+
+        elsewhere.block_hash = chain.blocks[1].hash.clone();
+        assert_eq!(elsewhere.verify(&chain, &documents()[0]), Err(ChainError::MissingAnchor { index: 1 }));
+
+        let mut missing = receipts[0].clone();
+        missing.block_index = 7;
+        assert_eq!(
+            missing.verify(&chain, &documents()[0]),
+            Err(ChainError::BlockNotFound { index: 7, len: 3 })
+        );
+
+        // Rewriting the anchored root breaks the chain itself.
+        chain.blocks[2].entries[0] = format!("{}5:{}", ANCHOR_PREFIX, "0".repeat(128));
+        assert!(matches!(
+            receipts[0].verify(&chain, &documents()[0]),
+            Err(ChainError::MerkleRootMismatch { index: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn test_batches_anchor_independently() {
+        let (mut chain, first) = anchored_chain();
+        let mut aggregator = Aggregator::new();
+
+        assert_eq!(aggregator.anchor(&mut chain, false), Err(ChainError::NoPendingDigests));
+
+        aggregator.submit(b"Later document");
+        let second = aggregator.anchor(&mut chain, false).unwrap();
+
+        assert_eq!(second.len(), 1);
+        assert_eq!(second[0].block_index, 3);
+        assert_eq!(second[0].verify(&chain, b"Later document"), Ok(()));
+        assert_eq!(first[4].verify(&chain, &documents()[4]), Ok(()));
+
+        let json = serde_json::to_string(&second[0]).unwrap();
+        let decoded: Receipt = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.verify(&chain, b"Later document"), Ok(()));
+    }
+}
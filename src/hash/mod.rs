@@ -0,0 +1,2 @@
+pub mod sha256;
+pub mod sha512;
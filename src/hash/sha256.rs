@@ -0,0 +1,187 @@
+const INITIAL_HASHES: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a,
+    0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+const ROUND_CONSTANTS: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+#[inline]
+fn lowercase_sigma0(x: u32) -> u32 {
+    x.rotate_right(7) ^ x.rotate_right(18) ^ (x >> 3)
+}
+
+#[inline]
+fn lowercase_sigma1(x: u32) -> u32 {
+    x.rotate_right(17) ^ x.rotate_right(19) ^ (x >> 10)
+}
+
+#[inline]
+fn uppercase_sigma0(x: u32) -> u32 {
+    x.rotate_right(2) ^ x.rotate_right(13) ^ x.rotate_right(22)
+}
+
+#[inline]
+fn uppercase_sigma1(x: u32) -> u32 {
+    x.rotate_right(6) ^ x.rotate_right(11) ^ x.rotate_right(25)
+}
+
+#[inline]
+fn ch(x: u32, y: u32, z: u32) -> u32 {
+    (x & y) ^ ((!x) & z)
+}
+
+#[inline]
+fn maj(x: u32, y: u32, z: u32) -> u32 {
+    (x & y) ^ (x & z) ^ (y & z)
+}
+
+pub struct SHA256;
+
+impl SHA256 {
+
+    pub fn hash(data: &[u8]) -> [u8; 32] {
+        let padded = Self::preprocess(data);
+
+        let mut hash_values = INITIAL_HASHES;
+
+        for block in padded.chunks(64) {
+            Self::compress_block(&mut hash_values, block);
+        }
+
+        Self::finalize(&hash_values)
+    }
+
+    // SHA-256 applied twice, as used for Bitcoin txids, block hashes and
+    // Merkle nodes.
+    pub fn double_hash(data: &[u8]) -> [u8; 32] {
+        Self::hash(&Self::hash(data))
+    }
+
+    fn preprocess(data: &[u8]) -> Vec<u8> {
+        let original_bits = (data.len() as u64).wrapping_mul(8);
+
+        let mut padded = data.to_vec();
+
+        padded.push(0x80);
+
+        while !(padded.len() + 8).is_multiple_of(64) {
+            padded.push(0x00);
+        }
+
+        padded.extend_from_slice(&original_bits.to_be_bytes());
+
+        padded
+    }
+
+    fn compress_block(hash_values: &mut [u32; 8], block: &[u8]) {
+        let mut w = [0u32; 64];
+
+        for (i, word) in block.chunks_exact(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+
+        for i in 16..64 {
+            w[i] = lowercase_sigma1(w[i - 2])
+                .wrapping_add(w[i - 7])
+                .wrapping_add(lowercase_sigma0(w[i - 15]))
+                .wrapping_add(w[i - 16]);
+        }
+
+        let mut a = hash_values[0];
+        let mut b = hash_values[1];
+        let mut c = hash_values[2];
+        let mut d = hash_values[3];
+        let mut e = hash_values[4];
+        let mut f = hash_values[5];
+        let mut g = hash_values[6];
+        let mut h = hash_values[7];
+
+        for i in 0..64 {
+            let t1 = h
+                .wrapping_add(uppercase_sigma1(e))
+                .wrapping_add(ch(e, f, g))
+                .wrapping_add(ROUND_CONSTANTS[i])
+                .wrapping_add(w[i]);
+
+            let t2 = uppercase_sigma0(a).wrapping_add(maj(a, b, c));
+
+            h = g;
+            g = f;
+            f = e;
+            e = d.wrapping_add(t1);
+            d = c;
+            c = b;
+            b = a;
+            a = t1.wrapping_add(t2);
+        }
+
+        hash_values[0] = hash_values[0].wrapping_add(a);
+        hash_values[1] = hash_values[1].wrapping_add(b);
+        hash_values[2] = hash_values[2].wrapping_add(c);
+        hash_values[3] = hash_values[3].wrapping_add(d);
+        hash_values[4] = hash_values[4].wrapping_add(e);
+        hash_values[5] = hash_values[5].wrapping_add(f);
+        hash_values[6] = hash_values[6].wrapping_add(g);
+        hash_values[7] = hash_values[7].wrapping_add(h);
+    }
+
+    fn finalize(hash_values: &[u32; 8]) -> [u8; 32] {
+        let mut result = [0u8; 32];
+
+        for (i, value) in hash_values.iter().enumerate() {
+            result[i * 4..(i + 1) * 4].copy_from_slice(&value.to_be_bytes());
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sha256_empty_string() {
+        let expected = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+
+        assert_eq!(hex::encode(SHA256::hash(b"")), expected);
+    }
+
+    #[test]
+    fn test_sha256_abc() {
+        let expected = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+
+        assert_eq!(hex::encode(SHA256::hash(b"abc")), expected);
+    }
+
+    #[test]
+    fn test_sha256_two_block() {
+        let input = b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq";
+        let expected = "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1";
+
+        assert_eq!(hex::encode(SHA256::hash(input)), expected);
+    }
+
+    #[test]
+    fn test_sha256_long_message() {
+        let input = vec![b'a'; 1_000_000];
+        let expected = "cdc76e5c9914fb9281a1c7e284d73e67f1809a48a497200e046d39ccc7112cd0";
+
+        assert_eq!(hex::encode(SHA256::hash(&input)), expected);
+    }
+
+    #[test]
+    fn test_double_sha256() {
+        let expected = "9595c9df90075148eb06860365df33584b75bff782a510c6cd4883a419833d50";
+
+        assert_eq!(hex::encode(SHA256::double_hash(b"hello")), expected);
+    }
+}
@@ -1,9 +1,7 @@
-use std::fmt::Write;
-
-cosnt INITIAL_HASHES: [u64; 8] = [
-    0x6a9e667f3bcc908,
+const INITIAL_HASHES: [u64; 8] = [
+    0x6a09e667f3bcc908,
     0xbb67ae8584caa73b,
-    0xec6ef372fe94f82b,
+    0x3c6ef372fe94f82b,
     0xa54ff53a5f1d36f1,
     0x510e527fade682d1,
     0x9b05688c2b3e6c1f,
@@ -11,7 +9,7 @@ cosnt INITIAL_HASHES: [u64; 8] = [
     0x5be0cd19137e2179,
 ];
 
-const ROUNT_CONSTANTS: [u64, 80] = [
+const ROUND_CONSTANTS: [u64; 80] = [
     0x428a2f98d728ae22, 0x7137449123ef65cd, 0xb5c0fbcfec4d3b2f, 0xe9b5dba58189dbbc,
     0x3956c25bf348b538, 0x59f111f1b605d019, 0x923f82a4af194f9b, 0xab1c5ed5da6d8118,
     0xd807aa98a3030242, 0x12835b0145706fbe, 0x243185be4ee4b28c, 0x550c7dc3d5ffb4e2,
@@ -36,7 +34,7 @@ const ROUNT_CONSTANTS: [u64, 80] = [
 
 #[inline]
 fn right_rotate(value: u64, n: u32)-> u64 {
-    (value >> n) | (value << (64 - n))
+    value.rotate_right(n)
 }
 
 #[inline]
@@ -54,6 +52,16 @@ fn lowercase_sigma1(x: u64) -> u64 {
     right_rotate(x, 19) ^ right_rotate(x, 61) ^ right_shift(x, 6)
 }
 
+#[inline]
+fn uppercase_sigma0(x: u64) -> u64 {
+    right_rotate(x, 28) ^ right_rotate(x, 34) ^ right_rotate(x, 39)
+}
+
+#[inline]
+fn uppercase_sigma1(x: u64) -> u64 {
+    right_rotate(x, 14) ^ right_rotate(x, 18) ^ right_rotate(x, 41)
+}
+
 #[inline]
 fn ch(x: u64, y: u64, z: u64) -> u64 {
     (x & y) ^ ((!x) & z)
@@ -61,7 +69,7 @@ fn ch(x: u64, y: u64, z: u64) -> u64 {
 
 #[inline]
 fn maj(x: u64, y: u64, z: u64) -> u64 {
-    (x & y) ^ (x & z) & (y & z)
+    (x & y) ^ (x & z) ^ (y & z)
 }
 
 
@@ -73,7 +81,7 @@ impl SHA512 {
     
         let padded = Self::preprocess(data);
 
-        let mut hash_values = INITIAL_HASHES.clone();
+        let mut hash_values = INITIAL_HASHES;
 
         for block in padded.chunks(128) {
             Self::compress_block(&mut hash_values, block);
@@ -91,11 +99,10 @@ impl SHA512 {
         
         padded.push(0x80);
 
-        while (padded.len() + 16) % 128 != 0 {
+        while !(padded.len() + 16).is_multiple_of(128) {
             padded.push(0x00);
         }
 
-        padded.extend_from_slice(&0u64.to_be_bytes());
         padded.extend_from_slice(&original_bits.to_be_bytes());
 
         padded
@@ -117,7 +124,7 @@ impl SHA512 {
             w[i] = lowercase_sigma1(w[i - 2])
                 .wrapping_add(w[i - 7])
                 .wrapping_add(lowercase_sigma0(w[i -15]))
-                .wrapping_add(w[i - 16])
+                .wrapping_add(w[i - 16]);
         }
         
         let mut a = hash_values[0];
@@ -131,11 +138,10 @@ impl SHA512 {
 
         for i in 0..80 {
             let t1 = h 
-                .wrapping_add(uppercase_sigma1(0))
+                .wrapping_add(uppercase_sigma1(e))
                 .wrapping_add(ch(e, f, g))
                 .wrapping_add(ROUND_CONSTANTS[i])
-                .wrapping_add(w[i])
-
+                .wrapping_add(w[i]);
 
             let t2 = uppercase_sigma0(a).wrapping_add(maj(a, b, c));
 
@@ -172,6 +178,7 @@ impl SHA512 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::fmt::Write;
 
     fn hash_to_hex(hash: &[u8; 64]) -> String {
         let mut hex_string = String::new();
@@ -182,16 +189,16 @@ mod tests {
     }
     
     #[test]
-    fn test_sha512_empty_string() {
-        let input = b"";
+    fn test_sha512_abc() {
+        let input = b"abc";
         let expected = "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f";
 
         let hash = SHA512::hash(input);
         let result = hash_to_hex(&hash);
 
-        println("Input: \"abc\"");
-        println("Expected: {}", expected);
-        println("Got: {}", result);
+        println!("Input: \"abc\"");
+        println!("Expected: {}", expected);
+        println!("Got: {}", result);
 
         assert_eq!(result, expected, "SHA-512 has mismatch for 'abc'");
     }
@@ -213,7 +220,7 @@ mod tests {
     #[test]
     fn test_sha512_two_block() {
         let input = b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq";
-        let expected = "204a8fc6dda82f0a0ced7beb8e08a41657c16ef620dadf86e8e3a5e5da11abe5f8fbd80e66e5b7f3d66c7d4a370ef60a0c6e86a6c0d9f6ebf9b5f5d9e0c3a2d";
+        let expected = "204a8fc6dda82f0a0ced7beb8e08a41657c16ef468b228a8279be331a703c33596fd15c13b1b07f9aa1d3bea57789ca031ad85c7a71dd70354ec631238ca3445";
 
 
         let hash = SHA512::hash(input);
@@ -237,7 +244,7 @@ mod tests {
 
         assert_eq!(hash1, hash2, "Hash is not test_sha512_deterministic (run 1 vs 2)");
 
-        assert_eq!(hash1, hash2, "Hash is not deterministic (run 2 vs 3)");
+        assert_eq!(hash2, hash3, "Hash is not deterministic (run 2 vs 3)");
     }
 
     #[test]
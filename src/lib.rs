@@ -0,0 +1,4 @@
+pub mod blockchain;
+pub mod hash;
+pub mod merkle;
+pub mod sig;
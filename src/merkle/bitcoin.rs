@@ -0,0 +1,286 @@
+use crate::hash::sha256::SHA256;
+use crate::merkle::builder::MerkleTreeBuilder;
+use crate::merkle::error::{MerkleError, ProofError};
+use crate::merkle::mode::HashMode;
+use crate::merkle::tree::{MerkleProof, MerkleTree};
+
+pub const HEADER_SIZE: usize = 80;
+
+// Converts a hash from RPC display order to Bitcoin's internal byte order.
+// Hashes are checked with `HashMode::is_valid_hash` wherever they enter a
+// tree or a proof, so anything else here is a bug.
+pub(crate) fn to_internal(hash: &str) -> [u8; 32] {
+    let mut bytes: [u8; 32] = hex::decode(hash)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .expect("Bitcoin hashes are validated before hashing");
+    bytes.reverse();
+    bytes
+}
+
+fn canonical_hash(hash: &str) -> Option<String> {
+    let hash = hash.to_ascii_lowercase();
+    HashMode::Bitcoin.is_valid_hash(&hash).then_some(hash)
+}
+
+pub(crate) fn to_display(mut bytes: [u8; 32]) -> String {
+    bytes.reverse();
+    hex::encode(bytes)
+}
+
+impl MerkleTree {
+
+    // Builds the transaction tree of a block from its txids in the order they
+    // appear in the block, written the way `getblock` prints them.
+    pub fn from_txids<T: AsRef<str>>(txids: &[T]) -> Result<Self, MerkleError> {
+        let leaves = txids
+            .iter()
+            .map(|txid| canonical_hash(txid.as_ref()).ok_or_else(|| MerkleError::InvalidHash(txid.as_ref().to_string())))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(MerkleTreeBuilder::new()
+            .hash_mode(HashMode::Bitcoin)
+            .build_from_leaf_hashes(leaves))
+    }
+}
+
+pub fn header_merkle_root(header: &[u8]) -> Option<String> {
+    if header.len() != HEADER_SIZE {
+        return None;
+    }
+
+    let mut root = [0u8; 32];
+    root.copy_from_slice(&header[36..68]);
+    Some(to_display(root))
+}
+
+pub fn block_hash(header: &[u8]) -> Option<String> {
+    if header.len() != HEADER_SIZE {
+        return None;
+    }
+
+    Some(to_display(SHA256::double_hash(header)))
+}
+
+impl MerkleProof {
+
+    // The sibling hashes and position bits of the branch, in the form
+    // `merkleblock` partial trees and Electrum's `get_merkle` use.
+    pub fn to_merkle_branch(&self) -> Option<(Vec<String>, u32)> {
+        if self.mode != HashMode::Bitcoin || self.arity != 2 || self.proof_path.len() > 32 {
+            return None;
+        }
+
+        let index = self
+            .proof_path
+            .iter()
+            .enumerate()
+            .filter(|(_, element)| !element.is_right)
+            .map(|(level, _)| 1u32 << level)
+            .sum();
+
+        Some((self.proof_path.iter().map(|element| element.hash.clone()).collect(), index))
+    }
+}
+
+// Bit `i` of `index` is set when the node at level `i` is a right child.
+pub fn verify_merkle_branch(txid: &str, branch: &[String], index: u32, root: &str) -> Result<(), ProofError> {
+    let leaf_count = 1usize << branch.len().min(32);
+    if branch.len() > 32 || index as usize >= leaf_count {
+        return Err(ProofError::IndexOutOfRange {
+            index: index as usize,
+            leaf_count,
+        });
+    }
+
+    let canonical = |hash: &str| canonical_hash(hash).ok_or_else(|| ProofError::InvalidHash(hash.to_string()));
+    let mut current = canonical(txid)?;
+    let root = canonical(root)?;
+
+    for (level, sibling) in branch.iter().enumerate() {
+        let sibling = canonical(sibling)?;
+
+        current = if (index >> level) & 1 == 1 {
+            HashMode::Bitcoin.hash_children(&[&sibling, &current])
+        } else {
+            HashMode::Bitcoin.hash_children(&[&current, &sibling])
+        };
+    }
+
+    if current != root {
+        return Err(ProofError::RootMismatch {
+            expected: root,
+            actual: current,
+        });
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merkle::encoding::EncodingError;
+
+    struct BlockFixture {
+        height: u32,
+        merkle_root: &'static str,
+        txids: &'static [&'static str],
+    }
+
+    // Transaction ids and Merkle roots of mainnet blocks, as printed by
+    // `bitcoin-cli getblock`.
+    const BLOCKS: [BlockFixture; 3] = [
+        BlockFixture {
+            height: 0,
+            merkle_root: "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b",
+            txids: &["4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b"],
+        },
+        BlockFixture {
+            height: 170,
+            merkle_root: "7dac2c5666815c17a3b36427de37bb9d2e2c5ccec3f8633eb91a4205cb4c10ff",
+            txids: &[
+                "b1fea52486ce0c62bb442b530a3f0132b826c74e473d1f2c220bfa78111c5082",
+                "f4184fc596403b9d638783cf57adfe4c75c605f6356fbc91338530e9831e9e16",
+            ],
+        },
+        BlockFixture {
+            height: 100_000,
+            merkle_root: "f3e94742aca4b5ef85488dc37c06c3282295ffec960994b2c0d5ac2a25a95766",
+            txids: &[
+                "8c14f0db3df150123e6f3dbbf30f8b955a8249b62ac1d1ff16284aefa3d06d87",
+                "fff2525b8931402dd09222c50775608f75787bd2b87e56995a7bdd30f79702c4",
+                "6359f0868171b1d194cbee1af2f16ea598ae8fad666d9b012c8ed2b79a236ec4",
+                "e9a66845e05d5abc0ad04ec80f774a7e585c6e8db975962d069a522137b80c1d",
+            ],
+        },
+    ];
+
+    const GENESIS_HEADER: &str = "0100000000000000000000000000000000000000000000000000000000000000\
+                                  000000003ba3edfd7a7b12b27ac72c3e67768f617fc81bc3888a51323a9fb8aa\
+                                  4b1e5e4a29ab5f49ffff001d1dac2b7c";
+
+    #[test]
+    fn test_roots_match_mainnet_blocks() {
+        for block in &BLOCKS {
+            let tree = MerkleTree::from_txids(block.txids).unwrap();
+
+            assert_eq!(tree.root, block.merkle_root, "Root mismatch for block {}", block.height);
+        }
+    }
+
+    #[test]
+    fn test_genesis_header() {
+        let header = hex::decode(GENESIS_HEADER).unwrap();
+
+        assert_eq!(
+            block_hash(&header).unwrap(),
+            "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f"
+        );
+        assert_eq!(header_merkle_root(&header).unwrap(), MerkleTree::from_txids(BLOCKS[0].txids).unwrap().root);
+        assert!(header_merkle_root(&header[1..]).is_none());
+    }
+
+    #[test]
+    fn test_proofs_verify_as_merkle_branches() {
+        for block in &BLOCKS {
+            let tree = MerkleTree::from_txids(block.txids).unwrap();
+
+            for (i, txid) in block.txids.iter().enumerate() {
+                let proof = tree.get_proof(i).unwrap();
+                assert!(tree.verify_proof(&proof).is_ok());
+
+                let (branch, index) = proof.to_merkle_branch().expect("Bitcoin proofs have a branch form");
+                assert_eq!(index as usize, i);
+                assert_eq!(verify_merkle_branch(txid, &branch, index, block.merkle_root), Ok(()));
+
+                if !branch.is_empty() {
+                    assert!(verify_merkle_branch(txid, &branch, index ^ 1, block.merkle_root).is_err());
+                }
+            }
+        }
+
+        let sha512_proof = MerkleTree::new(vec!["a".to_string(), "b".to_string()]).get_proof(0).unwrap();
+        assert!(sha512_proof.to_merkle_branch().is_none());
+    }
+
+    #[test]
+    fn test_odd_levels_duplicate_last_txid() {
+        let txids = &BLOCKS[2].txids[..3];
+        let pair = |left: &str, right: &str| {
+            let mut bytes = to_internal(left).to_vec();
+            bytes.extend(to_internal(right));
+            to_display(SHA256::double_hash(&bytes))
+        };
+
+        let expected = pair(&pair(txids[0], txids[1]), &pair(txids[2], txids[2]));
+        let tree = MerkleTree::from_txids(txids).unwrap();
+        assert_eq!(tree.root, expected);
+
+        // The duplication makes [a, b, c] and [a, b, c, c] share a root
+        // (CVE-2012-2459), so the branch of the last txid verifies as index 3.
+        let mut padded = txids.to_vec();
+        padded.push(txids[2]);
+        assert_eq!(MerkleTree::from_txids(&padded).unwrap().root, expected);
+
+        let (branch, index) = tree.get_proof(2).unwrap().to_merkle_branch().unwrap();
+        assert_eq!(branch[0], txids[2]);
+        assert_eq!(verify_merkle_branch(txids[2], &branch, index, &expected), Ok(()));
+        assert_eq!(verify_merkle_branch(txids[2], &branch, index | 1, &expected), Ok(()));
+    }
+
+    #[test]
+    fn test_malformed_hashes_rejected() {
+        let txid = BLOCKS[1].txids[0];
+        let tree = MerkleTree::from_txids(BLOCKS[1].txids).unwrap();
+        let (branch, index) = tree.get_proof(0).unwrap().to_merkle_branch().unwrap();
+
+        // Uppercase is how some explorers print hashes, so it is accepted.
+        assert_eq!(
+            MerkleTree::from_txids(&[txid.to_uppercase(), BLOCKS[1].txids[1].to_string()]).unwrap(),
+            tree
+        );
+        assert_eq!(
+            verify_merkle_branch(&txid.to_uppercase(), &branch, index, BLOCKS[1].merkle_root),
+            Ok(())
+        );
+
+        for malformed in ["", "not hex", &txid[..62], &format!("{}00", txid)] {
+            assert_eq!(
+                MerkleTree::from_txids(&[malformed]),
+                Err(MerkleError::InvalidHash(malformed.to_string()))
+            );
+            assert_eq!(
+                verify_merkle_branch(malformed, &branch, index, BLOCKS[1].merkle_root),
+                Err(ProofError::InvalidHash(malformed.to_string()))
+            );
+        }
+
+        let mut short = branch.clone();
+        short[0].truncate(62);
+        assert_eq!(
+            verify_merkle_branch(txid, &short, index, BLOCKS[1].merkle_root),
+            Err(ProofError::InvalidHash(short[0].clone()))
+        );
+        assert_eq!(
+            verify_merkle_branch(txid, &branch, 2, BLOCKS[1].merkle_root),
+            Err(ProofError::IndexOutOfRange { index: 2, leaf_count: 2 })
+        );
+
+        let mut proof = tree.get_proof(1).unwrap();
+        proof.proof_path[0].hash.push_str("00");
+        assert!(matches!(tree.verify_proof(&proof), Err(ProofError::InvalidHash(_))));
+    }
+
+    #[test]
+    fn test_bitcoin_tree_serialization() {
+        let tree = MerkleTree::from_txids(BLOCKS[2].txids).unwrap();
+
+        let json = serde_json::to_string(&tree).unwrap();
+        let decoded: MerkleTree = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.mode, HashMode::Bitcoin);
+        assert_eq!(decoded, tree);
+
+        assert_eq!(tree.to_bytes(), Err(EncodingError::UnsupportedHashMode(HashMode::Bitcoin)));
+    }
+}
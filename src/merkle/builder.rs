@@ -0,0 +1,319 @@
+use crate::merkle::mode::HashMode;
+use crate::merkle::node_store::{MemoryStore, NodeStore};
+use crate::merkle::tree::{MerkleTree, OddNodePolicy, DEFAULT_ARITY};
+use std::fmt;
+use std::io;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BuilderError {
+    InvalidArity(usize),
+    PromoteRequiresBinary { arity: usize },
+}
+
+impl fmt::Display for BuilderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuilderError::InvalidArity(arity) => write!(f, "arity must be at least 2, got {}", arity),
+            BuilderError::PromoteRequiresBinary { arity } => {
+                write!(f, "Promote policy requires an arity of 2, got {}", arity)
+            }
+        }
+    }
+}
+
+impl std::error::Error for BuilderError {}
+
+// Fields are private so every builder goes through the checks in the setters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MerkleTreeBuilder {
+    arity: usize,
+    policy: OddNodePolicy,
+    mode: HashMode,
+}
+
+impl Default for MerkleTreeBuilder {
+    fn default() -> Self {
+        MerkleTreeBuilder {
+            arity: DEFAULT_ARITY,
+            policy: OddNodePolicy::Duplicate,
+            mode: HashMode::Sha512,
+        }
+    }
+}
+
+impl MerkleTreeBuilder {
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn arity(mut self, arity: usize) -> Result<Self, BuilderError> {
+        if arity < 2 {
+            return Err(BuilderError::InvalidArity(arity));
+        }
+        self.arity = arity;
+        self.check_promote()
+    }
+
+    pub fn odd_node_policy(mut self, policy: OddNodePolicy) -> Result<Self, BuilderError> {
+        self.policy = policy;
+        self.check_promote()
+    }
+
+    pub fn hash_mode(mut self, mode: HashMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    // Promoting a short group of several nodes would leave a level with fewer
+    // than `arity - 1` siblings, which the proof format cannot express.
+    fn check_promote(self) -> Result<Self, BuilderError> {
+        if self.policy == OddNodePolicy::Promote && self.arity != 2 {
+            return Err(BuilderError::PromoteRequiresBinary { arity: self.arity });
+        }
+        Ok(self)
+    }
+
+    pub fn build(&self, data: Vec<String>) -> MerkleTree {
+        self.build_from_leaf_hashes(MerkleTree::hash_leaves(&data, self.mode))
+    }
+
+    pub fn build_from_leaf_hashes(&self, leaf_hashes: Vec<String>) -> MerkleTree {
+        MerkleTree::build(MemoryStore::new(), leaf_hashes, self.arity, self.policy, self.mode)
+            .expect("In-memory node store should not fail")
+    }
+
+    pub fn build_with_store<S: NodeStore>(&self, store: S, data: Vec<String>) -> io::Result<MerkleTree<S>> {
+        let leaf_hashes = MerkleTree::hash_leaves(&data, self.mode);
+        MerkleTree::build(store, leaf_hashes, self.arity, self.policy, self.mode)
+    }
+
+    // Reopens a store written by a tree with the same settings.
+    pub fn open_store<S: NodeStore>(&self, store: S) -> io::Result<MerkleTree<S>> {
+        MerkleTree::from_store_with(store, self.arity, self.policy, self.mode)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merkle::error::ProofError;
+    use crate::merkle::node_store::FileStore;
+    use std::collections::HashSet;
+
+    const ARITIES: [usize; 4] = [2, 4, 8, 16];
+
+    fn records(count: usize) -> Vec<String> {
+        (0..count).map(|i| format!("Record {}", i)).collect()
+    }
+
+    #[test]
+    fn test_binary_builder_matches_default_tree() {
+        for count in 0..=20 {
+            let tree = MerkleTreeBuilder::new().arity(2).unwrap().build(records(count));
+
+            assert_eq!(tree, MerkleTree::new(records(count)), "Mismatch for {} leaves", count);
+        }
+    }
+
+    #[test]
+    fn test_wider_trees_are_shallower() {
+        let data = records(1000);
+
+        let heights: Vec<usize> = ARITIES
+            .iter()
+            .map(|&arity| MerkleTreeBuilder::new().arity(arity).unwrap().build(data.clone()).height())
+            .collect();
+
+        assert_eq!(heights, vec![11, 6, 5, 4]);
+    }
+
+    #[test]
+    fn test_k_ary_proofs_verify() {
+        for arity in ARITIES {
+            for count in [1, 2, 3, arity - 1, arity, arity + 1, arity * arity + 3, 100] {
+                let tree = MerkleTreeBuilder::new().arity(arity).unwrap().build(records(count));
+
+                for i in 0..count {
+                    let proof = tree.get_proof(i).expect("Should generate proof");
+
+                    assert_eq!(proof.arity, arity);
+                    assert_eq!(proof.proof_path.len(), (tree.height() - 1) * (arity - 1));
+                    assert!(
+                        tree.verify_proof(&proof).is_ok(),
+                        "Proof for leaf {} of {} failed at arity {}",
+                        i,
+                        count,
+                        arity
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_tampered_k_ary_proof_detection() {
+        for arity in ARITIES {
+            let tree = MerkleTreeBuilder::new().arity(arity).unwrap().build(records(50));
+            let proof = tree.get_proof(17).unwrap();
+
+            let mut tampered = proof.clone();
+            tampered.proof_path[arity / 2].hash = "0".repeat(128);
+            assert!(matches!(tree.verify_proof(&tampered), Err(ProofError::RootMismatch { .. })));
+
+            // Moving the node within its group changes the parent hash.
+            let mut tampered = proof.clone();
+            for element in tampered.proof_path.iter_mut().take(arity - 1) {
+                element.is_right = !element.is_right;
+            }
+            assert!(matches!(tree.verify_proof(&tampered), Err(ProofError::RootMismatch { .. })));
+
+            let mut tampered = proof;
+            tampered.arity = arity * 2;
+            assert_eq!(
+                tree.verify_proof(&tampered),
+                Err(ProofError::ArityMismatch { expected: arity, actual: arity * 2 })
+            );
+        }
+    }
+
+    #[test]
+    fn test_k_ary_update_and_store() {
+        for arity in ARITIES {
+            let builder = MerkleTreeBuilder::new().arity(arity).unwrap();
+
+            let mut data = records(37);
+            let mut tree = builder.build(data.clone());
+            assert_eq!((tree.arity(), tree.policy(), tree.mode()), (arity, OddNodePolicy::Duplicate, HashMode::Sha512));
+
+            data[29] = "updated".to_string();
+            tree.update(29, "updated".to_string()).expect("Update should succeed");
+            assert_eq!(tree, builder.build(data.clone()));
+
+            let path = std::env::temp_dir().join(format!("merkle-builder-{}-{}.log", std::process::id(), arity));
+            let stored = builder
+                .build_with_store(FileStore::create(&path).unwrap(), data)
+                .expect("File store should accept nodes");
+            assert_eq!(stored.root, tree.root);
+
+            let reopened = builder.open_store(FileStore::open(&path).unwrap()).unwrap();
+            assert!(reopened.verify_proof(&tree.get_proof(29).unwrap()).is_ok());
+
+            std::fs::remove_file(&path).ok();
+        }
+    }
+
+    const POLICIES: [OddNodePolicy; 4] = [
+        OddNodePolicy::Duplicate,
+        OddNodePolicy::Promote,
+        OddNodePolicy::PadZero,
+        OddNodePolicy::PadEmptyLeaf,
+    ];
+
+    fn leaf(i: usize) -> String {
+        MerkleTree::hash_leaf(format!("Record {}", i).as_bytes())
+    }
+
+    #[test]
+    fn test_promote_carries_lone_nodes_up() {
+        let tree = MerkleTreeBuilder::new()
+            .odd_node_policy(OddNodePolicy::Promote).unwrap()
+            .build(records(5));
+
+        let left = MerkleTree::hash_pair(
+            &MerkleTree::hash_pair(&leaf(0), &leaf(1)),
+            &MerkleTree::hash_pair(&leaf(2), &leaf(3)),
+        );
+        assert_eq!(tree.root, MerkleTree::hash_pair(&left, &leaf(4)));
+
+        // The promoted leaf needs a single sibling instead of three.
+        assert_eq!(tree.get_proof(4).unwrap().proof_path.len(), 1);
+    }
+
+    #[test]
+    fn test_padding_matches_power_of_arity_leaves() {
+        for (policy, pad) in [
+            (OddNodePolicy::PadZero, "0".repeat(128)),
+            (OddNodePolicy::PadEmptyLeaf, MerkleTree::hash_leaf(b"")),
+        ] {
+            for arity in [2, 4] {
+                for count in [3, 5, 6, 17] {
+                    let builder = MerkleTreeBuilder::new().arity(arity).unwrap().odd_node_policy(policy).unwrap();
+                    let tree = builder.build(records(count));
+
+                    let mut leaves: Vec<String> = (0..count).map(leaf).collect();
+                    let full = arity.pow((tree.height() - 1) as u32);
+                    leaves.resize(full, pad.clone());
+
+                    let padded = MerkleTreeBuilder::new().arity(arity).unwrap().build_from_leaf_hashes(leaves);
+                    assert_eq!(tree.root, padded.root, "{:?} mismatch for {} leaves at arity {}", policy, count, arity);
+                    assert_eq!(tree.leaf_count(), count);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_every_policy_proves_and_updates() {
+        for policy in POLICIES {
+            let arities: &[usize] = if policy == OddNodePolicy::Promote { &[2] } else { &ARITIES };
+
+            for &arity in arities {
+                let builder = MerkleTreeBuilder::new().arity(arity).unwrap().odd_node_policy(policy).unwrap();
+
+                for count in 1..=33 {
+                    let mut data = records(count);
+                    let mut tree = builder.build(data.clone());
+
+                    for i in 0..count {
+                        let proof = tree.get_proof(i).expect("Should generate proof");
+                        assert!(
+                            tree.verify_proof(&proof).is_ok(),
+                            "{:?} proof for leaf {} of {} failed at arity {}",
+                            policy,
+                            i,
+                            count,
+                            arity
+                        );
+                    }
+
+                    data[count - 1] = "updated".to_string();
+                    tree.update(count - 1, "updated".to_string()).unwrap();
+                    assert_eq!(tree.root, builder.build(data).root);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_policies_give_distinct_roots() {
+        let roots: HashSet<String> = POLICIES
+            .iter()
+            .map(|&policy| MerkleTreeBuilder::new().odd_node_policy(policy).unwrap().build(records(5)).root)
+            .collect();
+        assert_eq!(roots.len(), POLICIES.len());
+
+        // Full trees have no odd levels, so every policy agrees.
+        let roots: HashSet<String> = POLICIES
+            .iter()
+            .map(|&policy| MerkleTreeBuilder::new().odd_node_policy(policy).unwrap().build(records(8)).root)
+            .collect();
+        assert_eq!(roots.len(), 1);
+    }
+
+    #[test]
+    fn test_invalid_settings_rejected() {
+        let promoted = MerkleTreeBuilder::new().odd_node_policy(OddNodePolicy::Promote).unwrap();
+        assert_eq!(promoted.arity(4), Err(BuilderError::PromoteRequiresBinary { arity: 4 }));
+
+        let wide = MerkleTreeBuilder::new().arity(4).unwrap();
+        assert_eq!(
+            wide.odd_node_policy(OddNodePolicy::Promote),
+            Err(BuilderError::PromoteRequiresBinary { arity: 4 })
+        );
+
+        for arity in [0, 1] {
+            assert_eq!(MerkleTreeBuilder::new().arity(arity), Err(BuilderError::InvalidArity(arity)));
+        }
+    }
+}
@@ -0,0 +1,391 @@
+use crate::merkle::error::{MerkleError, ProofError};
+use crate::merkle::mode::HashMode;
+use crate::merkle::tree::{MerkleProof, MerkleTree, DEFAULT_ARITY};
+use std::fmt;
+use std::io::{self, Read};
+
+// Chunks are buffered whole while they are cut, so their size is capped.
+pub const MAX_CHUNK_SIZE: usize = 64 * 1024 * 1024;
+
+// Random values indexed by byte for the rolling gear hash. They are derived
+// with splitmix64 from a fixed seed so chunk boundaries never change between
+// builds.
+const GEAR: [u64; 256] = gear_table();
+
+const fn gear_table() -> [u64; 256] {
+    let mut table = [0u64; 256];
+    let mut state: u64 = 0x6d65726b6c652d63;
+    let mut i = 0;
+
+    while i < 256 {
+        state = state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        table[i] = z ^ (z >> 31);
+        i += 1;
+    }
+
+    table
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChunkerError {
+    InvalidSizes { min: usize, avg: usize, max: usize },
+    AverageTooSmall(usize),
+    MaxTooLarge(usize),
+}
+
+impl fmt::Display for ChunkerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChunkerError::InvalidSizes { min, avg, max } => {
+                write!(f, "chunk sizes must satisfy 0 < min <= avg <= max, got {}, {}, {}", min, avg, max)
+            }
+            ChunkerError::AverageTooSmall(avg) => write!(f, "average chunk size must be at least 16 bytes, got {}", avg),
+            ChunkerError::MaxTooLarge(max) => {
+                write!(f, "maximum chunk size must be at most {} bytes, got {}", MAX_CHUNK_SIZE, max)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ChunkerError {}
+
+// Fields are private so every config goes through the checks in `new`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkerConfig {
+    min_size: usize,
+    avg_size: usize,
+    max_size: usize,
+}
+
+impl Default for ChunkerConfig {
+    fn default() -> Self {
+        ChunkerConfig {
+            min_size: 2 * 1024,
+            avg_size: 8 * 1024,
+            max_size: 64 * 1024,
+        }
+    }
+}
+
+impl ChunkerConfig {
+
+    pub fn new(min_size: usize, avg_size: usize, max_size: usize) -> Result<Self, ChunkerError> {
+        if !(0 < min_size && min_size <= avg_size && avg_size <= max_size) {
+            return Err(ChunkerError::InvalidSizes {
+                min: min_size,
+                avg: avg_size,
+                max: max_size,
+            });
+        }
+        if avg_size < 16 {
+            return Err(ChunkerError::AverageTooSmall(avg_size));
+        }
+        if max_size > MAX_CHUNK_SIZE {
+            return Err(ChunkerError::MaxTooLarge(max_size));
+        }
+
+        Ok(ChunkerConfig { min_size, avg_size, max_size })
+    }
+
+    pub fn min_size(&self) -> usize {
+        self.min_size
+    }
+
+    pub fn avg_size(&self) -> usize {
+        self.avg_size
+    }
+
+    pub fn max_size(&self) -> usize {
+        self.max_size
+    }
+
+    // Normalized chunking: a stricter mask before the average size and a
+    // looser one after it pulls chunk sizes towards the average. The
+    // saturating shifts keep the masks defined for any average size.
+    fn masks(&self) -> (u64, u64) {
+        let bits = self.avg_size.max(1).ilog2();
+        let mask = |ones: u32| (!0u64).checked_shl(64u32.saturating_sub(ones)).unwrap_or(0);
+        (mask(bits + 2), mask(bits.saturating_sub(2)))
+    }
+}
+
+// Returns the length of the chunk starting at `data[0]`. `data` must hold at
+// least `max_size` bytes unless it is the tail of the stream.
+pub fn cut_point(data: &[u8], config: &ChunkerConfig) -> usize {
+    if data.len() <= config.min_size {
+        return data.len();
+    }
+
+    let end = data.len().min(config.max_size);
+    let normal = config.avg_size.min(end);
+    let (mask_small, mask_large) = config.masks();
+    let mut hash = 0u64;
+
+    for (i, &byte) in data.iter().enumerate().take(end).skip(config.min_size) {
+        hash = (hash << 1).wrapping_add(GEAR[byte as usize]);
+
+        let mask = if i < normal { mask_small } else { mask_large };
+        if hash & mask == 0 {
+            return i + 1;
+        }
+    }
+
+    end
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chunk {
+    pub offset: usize,
+    pub len: usize,
+    pub hash: String,
+}
+
+// Each chunk's SHA512 digest is a leaf of the tree, so a chunk can be proven
+// against the file root on its own.
+#[derive(Debug, Clone)]
+pub struct ChunkedFile {
+    pub config: ChunkerConfig,
+    pub chunks: Vec<Chunk>,
+    pub tree: MerkleTree,
+}
+
+impl ChunkedFile {
+
+    pub fn from_bytes(data: &[u8], config: ChunkerConfig) -> Self {
+        Self::from_reader(data, config).expect("Reading from a slice should not fail")
+    }
+
+    pub fn from_reader<R: Read>(mut reader: R, config: ChunkerConfig) -> io::Result<Self> {
+        let mut chunks = Vec::new();
+        let mut buffer = Vec::with_capacity(config.max_size);
+        let mut offset = 0;
+        let mut eof = false;
+
+        loop {
+            if !eof && buffer.len() < config.max_size {
+                let wanted = (config.max_size - buffer.len()) as u64;
+                (&mut reader).take(wanted).read_to_end(&mut buffer)?;
+                eof = buffer.len() < config.max_size;
+            }
+
+            if buffer.is_empty() {
+                break;
+            }
+
+            let len = cut_point(&buffer, &config);
+            chunks.push(Chunk {
+                offset,
+                len,
+                hash: MerkleTree::hash_leaf(&buffer[..len]),
+            });
+
+            buffer.drain(..len);
+            offset += len;
+        }
+
+        let tree = MerkleTree::from_leaf_hashes(chunks.iter().map(|chunk| chunk.hash.clone()).collect());
+
+        Ok(ChunkedFile { config, chunks, tree })
+    }
+
+    pub fn root(&self) -> &str {
+        &self.tree.root
+    }
+
+    pub fn len(&self) -> usize {
+        self.chunks.iter().map(|chunk| chunk.len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    pub fn get_proof(&self, chunk_index: usize) -> Result<MerkleProof, MerkleError> {
+        self.tree.get_proof(chunk_index)
+    }
+
+    // `chunk_count` is published with the root. It pins the proof to a
+    // full-depth path, so bytes that hash to an inner node cannot pass for a
+    // chunk.
+    pub fn verify_chunk(root: &str, chunk_count: usize, chunk: &[u8], proof: &MerkleProof) -> Result<(), ProofError> {
+        proof.check_shape(DEFAULT_ARITY, HashMode::Sha512, None)?;
+        proof.check_duplicated_path(chunk_count)?;
+        proof.check_leaf(Some(MerkleTree::hash_leaf(chunk)), chunk_count)?;
+        proof.verify(root)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    fn small_config() -> ChunkerConfig {
+        ChunkerConfig::new(256, 1024, 4096).unwrap()
+    }
+
+    fn random_bytes(seed: u64, len: usize) -> Vec<u8> {
+        let mut state = seed;
+        (0..len)
+            .map(|_| {
+                state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                (state >> 56) as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_chunks_cover_input() {
+        let data = random_bytes(7, 100_000);
+        let config = small_config();
+        let file = ChunkedFile::from_bytes(&data, config);
+
+        let mut offset = 0;
+        for (i, chunk) in file.chunks.iter().enumerate() {
+            assert_eq!(chunk.offset, offset);
+            assert!(chunk.len <= config.max_size);
+            if i + 1 < file.chunks.len() {
+                assert!(chunk.len > config.min_size);
+            }
+            assert_eq!(chunk.hash, MerkleTree::hash_leaf(&data[offset..offset + chunk.len]));
+            offset += chunk.len;
+        }
+
+        assert_eq!(offset, data.len());
+        assert_eq!(file.len(), data.len());
+
+        let average = data.len() / file.chunks.len();
+        assert!(average > config.avg_size / 2 && average < config.avg_size * 2, "Average chunk size {}", average);
+    }
+
+    #[test]
+    fn test_masks_defined_for_any_average() {
+        assert_eq!(small_config().masks(), (!0u64 << 52, !0u64 << 56));
+
+        let largest = ChunkerConfig::new(16, MAX_CHUNK_SIZE, MAX_CHUNK_SIZE).unwrap();
+        assert_eq!(largest.masks(), (!0u64 << 36, !0u64 << 40));
+        assert_eq!(largest.avg_size(), MAX_CHUNK_SIZE);
+    }
+
+    #[test]
+    fn test_invalid_configs_rejected() {
+        assert_eq!(
+            ChunkerConfig::new(0, 1024, 4096),
+            Err(ChunkerError::InvalidSizes { min: 0, avg: 1024, max: 4096 })
+        );
+        assert_eq!(
+            ChunkerConfig::new(256, 128, 4096),
+            Err(ChunkerError::InvalidSizes { min: 256, avg: 128, max: 4096 })
+        );
+        assert_eq!(ChunkerConfig::new(4, 8, 4096), Err(ChunkerError::AverageTooSmall(8)));
+        assert_eq!(
+            ChunkerConfig::new(16, usize::MAX, usize::MAX),
+            Err(ChunkerError::MaxTooLarge(usize::MAX))
+        );
+    }
+
+    #[test]
+    fn test_reader_matches_slice() {
+        let data = random_bytes(11, 50_000);
+
+        // A reader that only returns a few bytes at a time must not move any
+        // boundaries.
+        struct Trickle<'a>(&'a [u8]);
+
+        impl Read for Trickle<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                let n = buf.len().min(self.0.len()).min(37);
+                buf[..n].copy_from_slice(&self.0[..n]);
+                self.0 = &self.0[n..];
+                Ok(n)
+            }
+        }
+
+        let from_slice = ChunkedFile::from_bytes(&data, small_config());
+        let from_reader = ChunkedFile::from_reader(Trickle(&data), small_config()).expect("Should read");
+
+        assert_eq!(from_reader.chunks, from_slice.chunks);
+        assert_eq!(from_reader.root(), from_slice.root());
+    }
+
+    #[test]
+    fn test_edit_preserves_most_chunks() {
+        let original = random_bytes(3, 200_000);
+        let mut edited = original.clone();
+        edited.splice(100_000..100_000, b"inserted bytes".iter().copied());
+
+        let before = ChunkedFile::from_bytes(&original, small_config());
+        let after = ChunkedFile::from_bytes(&edited, small_config());
+
+        let known: HashSet<&str> = before.chunks.iter().map(|chunk| chunk.hash.as_str()).collect();
+        let changed = after.chunks.iter().filter(|chunk| !known.contains(chunk.hash.as_str())).count();
+
+        assert_ne!(before.root(), after.root());
+        assert!((1..=3).contains(&changed), "{} chunks changed", changed);
+    }
+
+    #[test]
+    fn test_chunk_membership_proofs() {
+        let data = random_bytes(5, 30_000);
+        let file = ChunkedFile::from_bytes(&data, small_config());
+
+        for (i, chunk) in file.chunks.iter().enumerate() {
+            let bytes = &data[chunk.offset..chunk.offset + chunk.len];
+            let proof = file.get_proof(i).expect("Should generate proof");
+
+            assert!(ChunkedFile::verify_chunk(file.root(), file.chunks.len(), bytes, &proof).is_ok());
+            assert!(matches!(
+                ChunkedFile::verify_chunk(file.root(), file.chunks.len(), &bytes[1..], &proof),
+                Err(ProofError::LeafMismatch { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_inner_node_chunk_rejected() {
+        let data = random_bytes(5, 30_000);
+        let file = ChunkedFile::from_bytes(&data, small_config());
+        let count = file.chunks.len();
+
+        // The parent of the first two chunks is the SHA512 of their joined
+        // hashes, so those 256 bytes pass as a chunk one level up the tree.
+        let inner = format!("{}{}", file.chunks[0].hash, file.chunks[1].hash);
+        let mut proof = file.get_proof(0).unwrap();
+        proof.leaf_hash = MerkleTree::hash_leaf(inner.as_bytes());
+        proof.proof_path.remove(0);
+        assert_eq!(proof.compute_root().as_deref(), Ok(file.root()));
+
+        assert!(matches!(
+            ChunkedFile::verify_chunk(file.root(), count, inner.as_bytes(), &proof),
+            Err(ProofError::DepthMismatch { .. })
+        ));
+
+        let mut proof = file.get_proof(0).unwrap();
+        proof.leaf_index = usize::MAX;
+        assert_eq!(
+            ChunkedFile::verify_chunk(file.root(), count, &data[..file.chunks[0].len], &proof),
+            Err(ProofError::IndexOutOfRange { index: usize::MAX, leaf_count: count })
+        );
+
+        let mut proof = file.get_proof(0).unwrap();
+        proof.arity = 4;
+        assert_eq!(
+            ChunkedFile::verify_chunk(file.root(), count, &data[..file.chunks[0].len], &proof),
+            Err(ProofError::ArityMismatch { expected: 2, actual: 4 })
+        );
+    }
+
+    #[test]
+    fn test_small_and_empty_inputs() {
+        let empty = ChunkedFile::from_bytes(&[], small_config());
+        assert!(empty.is_empty());
+        assert_eq!(empty.root(), "");
+
+        let tiny = ChunkedFile::from_bytes(b"tiny file", small_config());
+        assert_eq!(tiny.chunks.len(), 1);
+        assert_eq!(tiny.root(), MerkleTree::hash_leaf(b"tiny file"));
+    }
+}
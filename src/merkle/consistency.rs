@@ -0,0 +1,268 @@
+use crate::merkle::error::{MerkleError, ProofError};
+use crate::merkle::node_store::NodeStore;
+use crate::merkle::tree::MerkleTree;
+
+// Consistency proofs follow the shape of RFC 9162 section 2.1.4, adapted to
+// this crate's odd-node rule: a node without a right sibling is paired with
+// itself instead of being promoted.
+
+impl<S: NodeStore> MerkleTree<S> {
+
+    pub fn root_at(&self, size: usize) -> Result<String, MerkleError> {
+        if !self.has_default_shape() {
+            return Err(MerkleError::UnsupportedShape);
+        }
+        Self::check_size(size, self.leaf_count())?;
+
+        self.node_at(size, Self::depth_for(size), 0)
+    }
+
+    pub fn consistency_proof(&self, old_size: usize, new_size: usize) -> Result<Vec<String>, MerkleError> {
+        if !self.has_default_shape() {
+            return Err(MerkleError::UnsupportedShape);
+        }
+        Self::check_size(new_size, self.leaf_count())?;
+        Self::check_size(old_size, new_size)?;
+
+        let mut proof = Vec::new();
+
+        if old_size == new_size {
+            return Ok(proof);
+        }
+
+        let mut level = old_size.trailing_zeros() as usize;
+        let mut index = (old_size - 1) >> level;
+
+        if !old_size.is_power_of_two() {
+            proof.push(self.node(level, index)?);
+        }
+
+        let mut new_len = new_size.div_ceil(1 << level);
+
+        while new_len > 1 {
+            if index % 2 == 1 {
+                proof.push(self.node(level, index - 1)?);
+            } else if index + 1 < new_len {
+                proof.push(self.node_at(new_size, level, index + 1)?);
+            }
+
+            index /= 2;
+            level += 1;
+            new_len = new_len.div_ceil(2);
+        }
+
+        Ok(proof)
+    }
+
+    fn check_size(size: usize, leaf_count: usize) -> Result<(), MerkleError> {
+        if size == 0 || size > leaf_count {
+            return Err(MerkleError::InvalidTreeSize { size, leaf_count });
+        }
+        Ok(())
+    }
+
+    pub(crate) fn depth_for(size: usize) -> usize {
+        let mut depth = 0;
+        let mut len = size;
+
+        while len > 1 {
+            len = len.div_ceil(2);
+            depth += 1;
+        }
+
+        depth
+    }
+
+    fn node_at(&self, size: usize, level: usize, index: usize) -> Result<String, MerkleError> {
+        if (index + 1) << level <= size {
+            return self.node(level, index);
+        }
+
+        let left = self.node_at(size, level - 1, index * 2)?;
+
+        if ((index * 2 + 1) << (level - 1)) < size {
+            let right = self.node_at(size, level - 1, index * 2 + 1)?;
+            Ok(MerkleTree::hash_pair(&left, &right))
+        } else {
+            Ok(MerkleTree::hash_pair(&left, &left))
+        }
+    }
+}
+
+pub fn verify_consistency(
+    old_root: &str,
+    new_root: &str,
+    old_size: usize,
+    new_size: usize,
+    proof: &[String],
+) -> Result<(), ProofError> {
+    let path_mismatch = ProofError::ConsistencyPathMismatch {
+        first: old_size,
+        second: new_size,
+    };
+
+    if old_size == 0 || old_size > new_size {
+        return Err(ProofError::InvalidTreeSizes {
+            first: old_size,
+            second: new_size,
+        });
+    }
+
+    if old_size == new_size {
+        if !proof.is_empty() {
+            return Err(path_mismatch);
+        }
+        return check_root(new_root, old_root.to_string());
+    }
+
+    let mut hashes = proof.iter();
+    let level = old_size.trailing_zeros();
+    let mut index = (old_size - 1) >> level;
+
+    let (mut old_hash, mut new_hash) = if old_size.is_power_of_two() {
+        (old_root.to_string(), old_root.to_string())
+    } else {
+        let hash = hashes.next().ok_or(path_mismatch.clone())?;
+        (hash.clone(), hash.clone())
+    };
+
+    let mut old_len = old_size >> level;
+    let mut new_len = new_size.div_ceil(1 << level);
+
+    while new_len > 1 {
+        if index % 2 == 1 {
+            let sibling = hashes.next().ok_or(path_mismatch.clone())?;
+            old_hash = MerkleTree::hash_pair(sibling, &old_hash);
+            new_hash = MerkleTree::hash_pair(sibling, &new_hash);
+        } else {
+            if index + 1 < new_len {
+                let sibling = hashes.next().ok_or(path_mismatch.clone())?;
+                new_hash = MerkleTree::hash_pair(&new_hash, sibling);
+            } else {
+                new_hash = MerkleTree::hash_pair(&new_hash, &new_hash);
+            }
+
+            if old_len > 1 {
+                old_hash = MerkleTree::hash_pair(&old_hash, &old_hash);
+            }
+        }
+
+        index /= 2;
+        old_len = old_len.div_ceil(2);
+        new_len = new_len.div_ceil(2);
+    }
+
+    if hashes.next().is_some() {
+        return Err(path_mismatch);
+    }
+
+    check_root(old_root, old_hash)?;
+    check_root(new_root, new_hash)
+}
+
+fn check_root(expected: &str, actual: String) -> Result<(), ProofError> {
+    if actual != expected {
+        return Err(ProofError::RootMismatch {
+            expected: expected.to_string(),
+            actual,
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leaves(count: usize) -> Vec<String> {
+        (0..count).map(|i| format!("Entry {}", i)).collect()
+    }
+
+    #[test]
+    fn test_root_at_matches_rebuilt_tree() {
+        let tree = MerkleTree::new(leaves(21));
+
+        for size in 1..=21 {
+            let expected = MerkleTree::new(leaves(size)).root;
+            assert_eq!(tree.root_at(size), Ok(expected), "Root mismatch at size {}", size);
+        }
+
+        assert_eq!(tree.root_at(0), Err(MerkleError::InvalidTreeSize { size: 0, leaf_count: 21 }));
+        assert_eq!(tree.root_at(22), Err(MerkleError::InvalidTreeSize { size: 22, leaf_count: 21 }));
+    }
+
+    #[test]
+    fn test_consistency_proofs_verify() {
+        let tree = MerkleTree::new(leaves(19));
+
+        for new_size in 1..=19 {
+            let new_root = tree.root_at(new_size).unwrap();
+
+            for old_size in 1..=new_size {
+                let old_root = tree.root_at(old_size).unwrap();
+                let proof = tree
+                    .consistency_proof(old_size, new_size)
+                    .expect("Should generate consistency proof");
+
+                assert_eq!(
+                    verify_consistency(&old_root, &new_root, old_size, new_size, &proof),
+                    Ok(()),
+                    "Proof from {} to {} should be valid",
+                    old_size,
+                    new_size
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_rewritten_history_detection() {
+        let tree = MerkleTree::new(leaves(12));
+
+        let mut forged = leaves(12);
+        forged[2] = "Rewritten".to_string();
+        let forged_tree = MerkleTree::new(forged);
+
+        let proof = forged_tree.consistency_proof(5, 12).unwrap();
+        let old_root = tree.root_at(5).unwrap();
+
+        assert!(matches!(
+            verify_consistency(&old_root, &forged_tree.root, 5, 12, &proof),
+            Err(ProofError::RootMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_tampered_consistency_proof_detection() {
+        let tree = MerkleTree::new(leaves(13));
+        let old_root = tree.root_at(6).unwrap();
+        let proof = tree.consistency_proof(6, 13).unwrap();
+
+        for i in 0..proof.len() {
+            let mut tampered = proof.clone();
+            tampered[i] = "0".repeat(128);
+            assert!(verify_consistency(&old_root, &tree.root, 6, 13, &tampered).is_err());
+        }
+
+        let mut extended = proof.clone();
+        extended.push("0".repeat(128));
+        assert_eq!(
+            verify_consistency(&old_root, &tree.root, 6, 13, &extended),
+            Err(ProofError::ConsistencyPathMismatch { first: 6, second: 13 })
+        );
+        assert_eq!(
+            verify_consistency(&old_root, &tree.root, 7, 6, &proof),
+            Err(ProofError::InvalidTreeSizes { first: 7, second: 6 })
+        );
+    }
+
+    #[test]
+    fn test_consistency_proof_invalid_sizes() {
+        let tree = MerkleTree::new(leaves(8));
+
+        assert_eq!(tree.consistency_proof(0, 4), Err(MerkleError::InvalidTreeSize { size: 0, leaf_count: 4 }));
+        assert_eq!(tree.consistency_proof(5, 4), Err(MerkleError::InvalidTreeSize { size: 5, leaf_count: 4 }));
+        assert_eq!(tree.consistency_proof(4, 9), Err(MerkleError::InvalidTreeSize { size: 9, leaf_count: 8 }));
+        assert_eq!(tree.consistency_proof(8, 8), Ok(Vec::new()));
+    }
+}
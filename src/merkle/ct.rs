@@ -0,0 +1,663 @@
+use crate::hash::sha256::SHA256;
+use crate::merkle::builder::MerkleTreeBuilder;
+use crate::merkle::error::{MerkleError, ProofError};
+use crate::merkle::mode::HashMode;
+use crate::merkle::node_store::NodeStore;
+use crate::merkle::tree::{MerkleTree, OddNodePolicy};
+use serde::{Deserialize, Serialize};
+
+// Root of the empty log, SHA-256 of the empty string.
+pub const EMPTY_ROOT: &str = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+
+const LEAF_PREFIX: u8 = 0x00;
+const NODE_PREFIX: u8 = 0x01;
+
+// Hashes are checked with `canonical` or `HashMode::is_valid_hash` before
+// they reach the hashing code, so anything else here is a bug.
+fn decode(hash: &str) -> [u8; 32] {
+    hex::decode(hash)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .expect("RFC 6962 hashes are validated before hashing")
+}
+
+fn canonical(hash: &str) -> Result<String, ProofError> {
+    let hash = hash.to_ascii_lowercase();
+    if !HashMode::Rfc6962.is_valid_hash(&hash) {
+        return Err(ProofError::InvalidHash(hash));
+    }
+    Ok(hash)
+}
+
+fn canonical_path(path: &[String]) -> Result<Vec<String>, ProofError> {
+    path.iter().map(|hash| canonical(hash)).collect()
+}
+
+pub fn leaf_hash(entry: &[u8]) -> String {
+    let mut bytes = Vec::with_capacity(entry.len() + 1);
+    bytes.push(LEAF_PREFIX);
+    bytes.extend_from_slice(entry);
+    hex::encode(SHA256::hash(&bytes))
+}
+
+pub(crate) fn hash_children(children: &[&str]) -> String {
+    let mut bytes = vec![NODE_PREFIX];
+    for child in children {
+        bytes.extend(decode(child));
+    }
+    hex::encode(SHA256::hash(&bytes))
+}
+
+fn node_hash(left: &str, right: &str) -> String {
+    hash_children(&[left, right])
+}
+
+// Largest power of two strictly smaller than `n`, the RFC 6962 split point.
+fn split_point(n: usize) -> usize {
+    let mut k = 1;
+    while k * 2 < n {
+        k *= 2;
+    }
+    k
+}
+
+// InclusionProofDataV2 from RFC 9162 section 4.12, without the log id.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InclusionProof {
+    pub leaf_index: u64,
+    pub tree_size: u64,
+    pub inclusion_path: Vec<String>,
+}
+
+// ConsistencyProofDataV2 from RFC 9162 section 4.11, without the log id.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConsistencyProof {
+    pub tree_size_1: u64,
+    pub tree_size_2: u64,
+    pub consistency_path: Vec<String>,
+}
+
+// TreeHeadDataV2 from RFC 9162 section 4.9. `extensions` holds the already
+// encoded `sth_extensions` list.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TreeHead {
+    pub timestamp: u64,
+    pub tree_size: u64,
+    pub root_hash: String,
+    pub extensions: Vec<u8>,
+}
+
+// SignedTreeHeadDataV2 from RFC 9162 section 4.10. Producing and checking
+// `signature` is left to the caller's signature scheme.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedTreeHead {
+    pub log_id: Vec<u8>,
+    pub tree_head: TreeHead,
+    pub signature: Vec<u8>,
+}
+
+impl TreeHead {
+
+    // The TLS encoding of the tree head, which is what the log signs.
+    pub fn signed_data(&self) -> Option<Vec<u8>> {
+        let root = hex::decode(&self.root_hash).ok()?;
+        let root_len = u8::try_from(root.len()).ok()?;
+        let extensions_len = u16::try_from(self.extensions.len()).ok()?;
+
+        let mut bytes = Vec::with_capacity(8 + 8 + 1 + root.len() + 2 + self.extensions.len());
+        bytes.extend_from_slice(&self.timestamp.to_be_bytes());
+        bytes.extend_from_slice(&self.tree_size.to_be_bytes());
+        bytes.push(root_len);
+        bytes.extend_from_slice(&root);
+        bytes.extend_from_slice(&extensions_len.to_be_bytes());
+        bytes.extend_from_slice(&self.extensions);
+
+        Some(bytes)
+    }
+}
+
+impl MerkleTree {
+
+    pub fn from_ct_entries<T: AsRef<[u8]>>(entries: &[T]) -> Self {
+        MerkleTreeBuilder::new()
+            .hash_mode(HashMode::Rfc6962)
+            .odd_node_policy(OddNodePolicy::Promote)
+            .expect("Binary trees can promote")
+            .build_from_leaf_hashes(entries.iter().map(|entry| leaf_hash(entry.as_ref())).collect())
+    }
+}
+
+// Proofs can be requested for any earlier tree size. Every complete, aligned
+// power-of-two subtree is already a node in the store, so only the right
+// edge of an older tree is recomputed.
+impl<S: NodeStore> MerkleTree<S> {
+
+    fn is_ct(&self) -> bool {
+        self.arity == 2 && self.policy == OddNodePolicy::Promote && self.mode == HashMode::Rfc6962
+    }
+
+    pub fn ct_tree_head(&self, tree_size: u64, timestamp: u64) -> Result<TreeHead, MerkleError> {
+        if !self.is_ct() {
+            return Err(MerkleError::UnsupportedShape);
+        }
+        let size = usize::try_from(tree_size).unwrap_or(usize::MAX);
+        if size > self.leaf_count() {
+            return Err(MerkleError::InvalidTreeSize {
+                size,
+                leaf_count: self.leaf_count(),
+            });
+        }
+
+        let root_hash = if size == 0 {
+            EMPTY_ROOT.to_string()
+        } else {
+            self.ct_subtree_hash(0, size)?
+        };
+
+        Ok(TreeHead {
+            timestamp,
+            tree_size,
+            root_hash,
+            extensions: Vec::new(),
+        })
+    }
+
+    pub fn ct_inclusion_proof(&self, leaf_index: u64, tree_size: u64) -> Result<InclusionProof, MerkleError> {
+        if !self.is_ct() {
+            return Err(MerkleError::UnsupportedShape);
+        }
+        let index = usize::try_from(leaf_index).unwrap_or(usize::MAX);
+        let size = usize::try_from(tree_size).unwrap_or(usize::MAX);
+        if size > self.leaf_count() {
+            return Err(MerkleError::InvalidTreeSize {
+                size,
+                leaf_count: self.leaf_count(),
+            });
+        }
+        if index >= size {
+            return Err(MerkleError::IndexOutOfRange { index, leaf_count: size });
+        }
+
+        let mut inclusion_path = Vec::new();
+        self.ct_path(index, 0, size, &mut inclusion_path)?;
+
+        Ok(InclusionProof {
+            leaf_index,
+            tree_size,
+            inclusion_path,
+        })
+    }
+
+    pub fn ct_consistency_proof(&self, tree_size_1: u64, tree_size_2: u64) -> Result<ConsistencyProof, MerkleError> {
+        if !self.is_ct() {
+            return Err(MerkleError::UnsupportedShape);
+        }
+        let old_size = usize::try_from(tree_size_1).unwrap_or(usize::MAX);
+        let new_size = usize::try_from(tree_size_2).unwrap_or(usize::MAX);
+        if new_size == 0 || new_size > self.leaf_count() {
+            return Err(MerkleError::InvalidTreeSize {
+                size: new_size,
+                leaf_count: self.leaf_count(),
+            });
+        }
+        if old_size == 0 || old_size > new_size {
+            return Err(MerkleError::InvalidTreeSize {
+                size: old_size,
+                leaf_count: new_size,
+            });
+        }
+
+        let mut consistency_path = Vec::new();
+        if old_size < new_size {
+            self.ct_subproof(old_size, 0, new_size, true, &mut consistency_path)?;
+        }
+
+        Ok(ConsistencyProof {
+            tree_size_1,
+            tree_size_2,
+            consistency_path,
+        })
+    }
+
+    // MTH(D[start:end]) from RFC 9162 section 2.1.1.
+    fn ct_subtree_hash(&self, start: usize, end: usize) -> Result<String, MerkleError> {
+        let len = end - start;
+
+        if len.is_power_of_two() && start.is_multiple_of(len) {
+            let level = len.trailing_zeros() as usize;
+            return self.node(level, start >> level);
+        }
+
+        let k = split_point(len);
+        Ok(node_hash(
+            &self.ct_subtree_hash(start, start + k)?,
+            &self.ct_subtree_hash(start + k, end)?,
+        ))
+    }
+
+    // PATH(m, D[start:end]) from RFC 9162 section 2.1.3.1.
+    fn ct_path(&self, m: usize, start: usize, end: usize, path: &mut Vec<String>) -> Result<(), MerkleError> {
+        let len = end - start;
+        if len == 1 {
+            return Ok(());
+        }
+
+        let k = split_point(len);
+        if m < k {
+            self.ct_path(m, start, start + k, path)?;
+            path.push(self.ct_subtree_hash(start + k, end)?);
+        } else {
+            self.ct_path(m - k, start + k, end, path)?;
+            path.push(self.ct_subtree_hash(start, start + k)?);
+        }
+
+        Ok(())
+    }
+
+    // SUBPROOF(m, D[start:end], b) from RFC 9162 section 2.1.4.1.
+    fn ct_subproof(&self, m: usize, start: usize, end: usize, complete: bool, path: &mut Vec<String>) -> Result<(), MerkleError> {
+        let len = end - start;
+        if m == len {
+            if !complete {
+                path.push(self.ct_subtree_hash(start, end)?);
+            }
+            return Ok(());
+        }
+
+        let k = split_point(len);
+        if m <= k {
+            self.ct_subproof(m, start, start + k, complete, path)?;
+            path.push(self.ct_subtree_hash(start + k, end)?);
+        } else {
+            self.ct_subproof(m - k, start + k, end, false, path)?;
+            path.push(self.ct_subtree_hash(start, start + k)?);
+        }
+
+        Ok(())
+    }
+}
+
+impl InclusionProof {
+
+    // The verification algorithm of RFC 9162 section 2.1.3.2.
+    pub fn verify(&self, leaf_hash: &str, root_hash: &str) -> Result<(), ProofError> {
+        let path_mismatch = ProofError::PathMismatch {
+            index: self.leaf_index as usize,
+            leaf_count: self.tree_size as usize,
+        };
+
+        if self.leaf_index >= self.tree_size {
+            return Err(ProofError::IndexOutOfRange {
+                index: self.leaf_index as usize,
+                leaf_count: self.tree_size as usize,
+            });
+        }
+
+        let root_hash = canonical(root_hash)?;
+        let mut fn_ = self.leaf_index;
+        let mut sn = self.tree_size - 1;
+        let mut r = canonical(leaf_hash)?;
+
+        for p in &canonical_path(&self.inclusion_path)? {
+            if sn == 0 {
+                return Err(path_mismatch);
+            }
+
+            if fn_ & 1 == 1 || fn_ == sn {
+                r = node_hash(p, &r);
+                if fn_ & 1 == 0 {
+                    while fn_ & 1 == 0 && fn_ != 0 {
+                        fn_ >>= 1;
+                        sn >>= 1;
+                    }
+                }
+            } else {
+                r = node_hash(&r, p);
+            }
+
+            fn_ >>= 1;
+            sn >>= 1;
+        }
+
+        if sn != 0 {
+            return Err(path_mismatch);
+        }
+
+        if r != root_hash {
+            return Err(ProofError::RootMismatch {
+                expected: root_hash,
+                actual: r,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+impl ConsistencyProof {
+
+    // The verification algorithm of RFC 9162 section 2.1.4.2.
+    pub fn verify(&self, first_hash: &str, second_hash: &str) -> Result<(), ProofError> {
+        let path_mismatch = ProofError::ConsistencyPathMismatch {
+            first: self.tree_size_1 as usize,
+            second: self.tree_size_2 as usize,
+        };
+
+        if self.tree_size_1 == 0 || self.tree_size_1 > self.tree_size_2 {
+            return Err(ProofError::InvalidTreeSizes {
+                first: self.tree_size_1 as usize,
+                second: self.tree_size_2 as usize,
+            });
+        }
+
+        let first_hash = canonical(first_hash)?;
+        let second_hash = canonical(second_hash)?;
+        let mut path = canonical_path(&self.consistency_path)?;
+
+        if self.tree_size_1 == self.tree_size_2 {
+            if !path.is_empty() {
+                return Err(path_mismatch);
+            }
+            return check_root(&second_hash, first_hash.clone());
+        }
+
+        if self.tree_size_1.is_power_of_two() {
+            path.insert(0, first_hash.clone());
+        }
+
+        if path.len() < 2 {
+            return Err(path_mismatch);
+        }
+
+        let mut fn_ = self.tree_size_1 - 1;
+        let mut sn = self.tree_size_2 - 1;
+
+        while fn_ & 1 == 1 {
+            fn_ >>= 1;
+            sn >>= 1;
+        }
+
+        let mut fr = path[0].clone();
+        let mut sr = fr.clone();
+
+        for c in &path[1..] {
+            if sn == 0 {
+                return Err(path_mismatch);
+            }
+
+            if fn_ & 1 == 1 || fn_ == sn {
+                fr = node_hash(c, &fr);
+                sr = node_hash(c, &sr);
+                if fn_ & 1 == 0 {
+                    while fn_ & 1 == 0 && fn_ != 0 {
+                        fn_ >>= 1;
+                        sn >>= 1;
+                    }
+                }
+            } else {
+                sr = node_hash(&sr, c);
+            }
+
+            fn_ >>= 1;
+            sn >>= 1;
+        }
+
+        if sn != 0 {
+            return Err(path_mismatch);
+        }
+
+        check_root(&first_hash, fr)?;
+        check_root(&second_hash, sr)
+    }
+}
+
+fn check_root(expected: &str, actual: String) -> Result<(), ProofError> {
+    if actual != expected {
+        return Err(ProofError::RootMismatch {
+            expected: expected.to_string(),
+            actual,
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Test vectors shared by the RFC 6962 reference implementations.
+    const ENTRIES: [&str; 8] = [
+        "",
+        "00",
+        "10",
+        "2021",
+        "3031",
+        "40414243",
+        "5051525354555657",
+        "606162636465666768696a6b6c6d6e6f",
+    ];
+
+    const ROOTS: [&str; 8] = [
+        "6e340b9cffb37a989ca544e6bb780a2c78901d3fb33738768511a30617afa01d",
+        "fac54203e7cc696cf0dfcb42c92a1d9dbaf70ad9e621f4bd8d98662f00e3c125",
+        "aeb6bcfe274b70a14fb067a5e5578264db0fa9b51af5e0ba159158f329e06e77",
+        "d37ee418976dd95753c1c73862b9398fa2a2cf9b4ff0fdfe8b30cd95209614b7",
+        "4e3bbb1f7b478dcfe71fb631631519a3bca12c9aefca1612bfce4c13a86264d4",
+        "76e67dadbcdf1e10e1b74ddc608abd2f98dfb16fbce75277b5232a127f2087ef",
+        "ddb89be403809e325750d3d263cd78929c2942b7942a34b77e122c9594a74c8c",
+        "5dc9da79a70659a9ad559cb701ded9a2ab9d823aad2f4960cfe370eff4604328",
+    ];
+
+    fn log() -> MerkleTree {
+        let entries: Vec<Vec<u8>> = ENTRIES.iter().map(|entry| hex::decode(entry).unwrap()).collect();
+        MerkleTree::from_ct_entries(&entries)
+    }
+
+    fn hashes(hashes: &[&str]) -> Vec<String> {
+        hashes.iter().map(|hash| hash.to_string()).collect()
+    }
+
+    #[test]
+    fn test_roots_match_rfc_vectors() {
+        let tree = log();
+        assert_eq!(tree.root, ROOTS[7]);
+
+        for size in 1..=8 {
+            let head = tree.ct_tree_head(size as u64, 0).unwrap();
+            assert_eq!(head.root_hash, ROOTS[size - 1], "Root mismatch for size {}", size);
+
+            // A log built from just the first entries has the same root.
+            assert_eq!(MerkleTree::from_ct_entries(&tree_entries(size)).root, ROOTS[size - 1]);
+        }
+
+        assert_eq!(tree.ct_tree_head(0, 0).unwrap().root_hash, EMPTY_ROOT);
+        assert_eq!(
+            tree.ct_tree_head(9, 0).unwrap_err(),
+            MerkleError::InvalidTreeSize { size: 9, leaf_count: 8 }
+        );
+        assert_eq!(
+            MerkleTree::new(vec!["a".to_string()]).ct_tree_head(1, 0).unwrap_err(),
+            MerkleError::UnsupportedShape
+        );
+    }
+
+    fn tree_entries(size: usize) -> Vec<Vec<u8>> {
+        ENTRIES[..size].iter().map(|entry| hex::decode(entry).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_inclusion_proof_vectors() {
+        let tree = log();
+        let cases = [
+            (0, 8, vec![
+                "96a296d224f285c67bee93c30f8a309157f0daa35dc5b87e410b78630a09cfc7",
+                "5f083f0a1a33ca076a95279832580db3e0ef4584bdff1f54c8a360f50de3031e",
+                "6b47aaf29ee3c2af9af889bc1fb9254dabd31177f16232dd6aab035ca39bf6e4",
+            ]),
+            (5, 8, vec![
+                "bc1a0643b12e4d2d7c77918f44e0f4f79a838b6cf9ec5b5c283e1f4d88599e6b",
+                "ca854ea128ed050b41b35ffc1b87b8eb2bde461e9e3b5596ece6b9d5975a0ae0",
+                "d37ee418976dd95753c1c73862b9398fa2a2cf9b4ff0fdfe8b30cd95209614b7",
+            ]),
+            (2, 3, vec!["fac54203e7cc696cf0dfcb42c92a1d9dbaf70ad9e621f4bd8d98662f00e3c125"]),
+            (1, 5, vec![
+                "6e340b9cffb37a989ca544e6bb780a2c78901d3fb33738768511a30617afa01d",
+                "5f083f0a1a33ca076a95279832580db3e0ef4584bdff1f54c8a360f50de3031e",
+                "bc1a0643b12e4d2d7c77918f44e0f4f79a838b6cf9ec5b5c283e1f4d88599e6b",
+            ]),
+        ];
+
+        for (index, size, path) in cases {
+            let proof = tree.ct_inclusion_proof(index, size).unwrap();
+            assert_eq!(proof.inclusion_path, hashes(&path));
+
+            let leaf = leaf_hash(&hex::decode(ENTRIES[index as usize]).unwrap());
+            assert_eq!(proof.verify(&leaf, ROOTS[size as usize - 1]), Ok(()));
+        }
+    }
+
+    #[test]
+    fn test_every_inclusion_proof_verifies() {
+        let tree = log();
+
+        for size in 1..=8u64 {
+            let root = &tree.ct_tree_head(size, 0).unwrap().root_hash;
+
+            for index in 0..size {
+                let proof = tree.ct_inclusion_proof(index, size).unwrap();
+                let leaf = tree.store.get(0, index as usize).unwrap().unwrap();
+                assert_eq!(proof.verify(&leaf, root), Ok(()), "Leaf {} of {} failed", index, size);
+
+                let mut wrong_index = proof.clone();
+                wrong_index.leaf_index = (index + 1) % size;
+                assert!(size == 1 || wrong_index.verify(&leaf, root).is_err());
+
+                let mut out_of_range = proof.clone();
+                out_of_range.tree_size = index;
+                assert!(out_of_range.verify(&leaf, root).is_err());
+
+                let mut extended = proof;
+                extended.inclusion_path.push(leaf.clone());
+                assert!(extended.verify(&leaf, root).is_err());
+            }
+        }
+
+        // The audit path of the full tree is the sibling list of a regular proof.
+        let proof = tree.get_proof(6).unwrap();
+        let path: Vec<String> = proof.proof_path.into_iter().map(|element| element.hash).collect();
+        assert_eq!(tree.ct_inclusion_proof(6, 8).unwrap().inclusion_path, path);
+    }
+
+    #[test]
+    fn test_consistency_proof_vectors() {
+        let tree = log();
+        let cases = [
+            (1, 1, vec![]),
+            (1, 8, vec![
+                "96a296d224f285c67bee93c30f8a309157f0daa35dc5b87e410b78630a09cfc7",
+                "5f083f0a1a33ca076a95279832580db3e0ef4584bdff1f54c8a360f50de3031e",
+                "6b47aaf29ee3c2af9af889bc1fb9254dabd31177f16232dd6aab035ca39bf6e4",
+            ]),
+            (6, 8, vec![
+                "0ebc5d3437fbe2db158b9f126a1d118e308181031d0a949f8dededebc558ef6a",
+                "ca854ea128ed050b41b35ffc1b87b8eb2bde461e9e3b5596ece6b9d5975a0ae0",
+                "d37ee418976dd95753c1c73862b9398fa2a2cf9b4ff0fdfe8b30cd95209614b7",
+            ]),
+            (2, 5, vec![
+                "5f083f0a1a33ca076a95279832580db3e0ef4584bdff1f54c8a360f50de3031e",
+                "bc1a0643b12e4d2d7c77918f44e0f4f79a838b6cf9ec5b5c283e1f4d88599e6b",
+            ]),
+        ];
+
+        for (old, new, path) in cases {
+            let proof = tree.ct_consistency_proof(old, new).unwrap();
+            assert_eq!(proof.consistency_path, hashes(&path));
+            assert_eq!(proof.verify(ROOTS[old as usize - 1], ROOTS[new as usize - 1]), Ok(()));
+        }
+
+        for old in 1..=8u64 {
+            for new in old..=8 {
+                let proof = tree.ct_consistency_proof(old, new).unwrap();
+                let old_root = ROOTS[old as usize - 1];
+                let new_root = ROOTS[new as usize - 1];
+
+                assert_eq!(proof.verify(old_root, new_root), Ok(()), "Consistency {} -> {} failed", old, new);
+
+                if old != new {
+                    assert!(proof.verify(new_root, old_root).is_err());
+                }
+            }
+        }
+
+        assert_eq!(
+            tree.ct_consistency_proof(0, 3).unwrap_err(),
+            MerkleError::InvalidTreeSize { size: 0, leaf_count: 3 }
+        );
+        assert_eq!(
+            tree.ct_consistency_proof(5, 4).unwrap_err(),
+            MerkleError::InvalidTreeSize { size: 5, leaf_count: 4 }
+        );
+    }
+
+    #[test]
+    fn test_verifiers_report_failures() {
+        let tree = log();
+        let leaf = tree.store.get(0, 5).unwrap().unwrap();
+        let proof = tree.ct_inclusion_proof(5, 8).unwrap();
+
+        assert_eq!(proof.verify(&leaf.to_uppercase(), &ROOTS[7].to_uppercase()), Ok(()));
+
+        // A path element that is not a 32-byte hash is not hashed as text.
+        for malformed in ["", "not hex", &ROOTS[0][..62], &format!("{}00", ROOTS[0])] {
+            let mut bad_path = proof.clone();
+            bad_path.inclusion_path[1] = malformed.to_string();
+            assert_eq!(bad_path.verify(&leaf, ROOTS[7]), Err(ProofError::InvalidHash(malformed.to_string())));
+            assert_eq!(proof.verify(&leaf, malformed), Err(ProofError::InvalidHash(malformed.to_string())));
+        }
+
+        let mut short = proof.clone();
+        short.inclusion_path.pop();
+        assert_eq!(short.verify(&leaf, ROOTS[7]), Err(ProofError::PathMismatch { index: 5, leaf_count: 8 }));
+        assert!(matches!(proof.verify(&leaf, ROOTS[6]), Err(ProofError::RootMismatch { .. })));
+
+        let consistency = tree.ct_consistency_proof(3, 7).unwrap();
+        assert_eq!(consistency.verify(ROOTS[2], ROOTS[6]), Ok(()));
+        assert!(matches!(consistency.verify(ROOTS[2], ROOTS[5]), Err(ProofError::RootMismatch { .. })));
+
+        let mut reversed = consistency.clone();
+        reversed.tree_size_1 = 8;
+        assert_eq!(
+            reversed.verify(ROOTS[7], ROOTS[6]),
+            Err(ProofError::InvalidTreeSizes { first: 8, second: 7 })
+        );
+
+        let mut truncated = consistency;
+        truncated.consistency_path.truncate(1);
+        assert_eq!(
+            truncated.verify(ROOTS[2], ROOTS[6]),
+            Err(ProofError::ConsistencyPathMismatch { first: 3, second: 7 })
+        );
+    }
+
+    #[test]
+    fn test_signed_tree_head() {
+        let tree = log();
+        let head = tree.ct_tree_head(8, 1_700_000_000_000).unwrap();
+        let data = head.signed_data().unwrap();
+
+        assert_eq!(data.len(), 8 + 8 + 1 + 32 + 2);
+        assert_eq!(&data[..8], &1_700_000_000_000u64.to_be_bytes());
+        assert_eq!(&data[8..16], &8u64.to_be_bytes());
+        assert_eq!(data[16], 32);
+        assert_eq!(hex::encode(&data[17..49]), ROOTS[7]);
+        assert_eq!(&data[49..], &[0, 0]);
+
+        let sth = SignedTreeHead {
+            log_id: vec![0x06, 0x03, 0x2b, 0x06, 0x01],
+            tree_head: head,
+            signature: vec![0xaa; 64],
+        };
+        let json = serde_json::to_string(&sth).unwrap();
+        assert_eq!(serde_json::from_str::<SignedTreeHead>(&json).unwrap(), sth);
+    }
+}
@@ -0,0 +1,162 @@
+use crate::merkle::node_store::NodeStore;
+use crate::merkle::tree::MerkleTree;
+use std::ops::Range;
+
+impl<S: NodeStore> MerkleTree<S> {
+
+    pub fn diff<T: NodeStore>(&self, other: &MerkleTree<T>) -> Vec<Range<usize>> {
+        let common = self.leaf_count().min(other.leaf_count());
+        let total = self.leaf_count().max(other.leaf_count());
+        let mut ranges = Vec::new();
+
+        // Trees of different shape share no interior nodes to compare.
+        if self.arity != other.arity || self.policy != other.policy || self.mode != other.mode {
+            if total > 0 {
+                ranges.push(0..total);
+            }
+            return ranges;
+        }
+
+        if common > 0 {
+            self.diff_node(other, common, self.height_for(common) - 1, 0, &mut ranges);
+        }
+
+        // Leaves past the shorter tree only exist on one side.
+        if common < total {
+            Self::push_range(&mut ranges, common..total);
+        }
+
+        ranges
+    }
+
+    // Nodes whose leaf range lies entirely inside the common prefix are the
+    // same subtree in both trees and can be compared directly. A node that
+    // straddles the end of the prefix mixes in padding or extra leaves, so it
+    // is always split.
+    fn diff_node<T: NodeStore>(
+        &self,
+        other: &MerkleTree<T>,
+        common: usize,
+        level: usize,
+        index: usize,
+        ranges: &mut Vec<Range<usize>>,
+    ) {
+        let span = self.arity.pow(level as u32);
+        let start = index * span;
+        if start >= common {
+            return;
+        }
+
+        let end = (index + 1) * span;
+
+        if end <= common {
+            // A node that cannot be read counts as different, so the leaves
+            // under it are still compared or reported.
+            let ours = self.store.get(level, index).ok().flatten();
+            if ours.is_some() && ours == other.store.get(level, index).ok().flatten() {
+                return;
+            }
+
+            if level == 0 {
+                Self::push_range(ranges, start..end);
+                return;
+            }
+        }
+
+        for child in index * self.arity..(index + 1) * self.arity {
+            self.diff_node(other, common, level - 1, child, ranges);
+        }
+    }
+
+    fn height_for(&self, leaf_count: usize) -> usize {
+        let mut height = 1;
+        let mut len = leaf_count;
+
+        while len > 1 {
+            len = len.div_ceil(self.arity);
+            height += 1;
+        }
+
+        height
+    }
+
+    fn push_range(ranges: &mut Vec<Range<usize>>, range: Range<usize>) {
+        match ranges.last_mut() {
+            Some(last) if last.end == range.start => last.end = range.end,
+            _ => ranges.push(range),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merkle::builder::MerkleTreeBuilder;
+
+    fn rows(count: usize) -> Vec<String> {
+        (0..count).map(|i| format!("Row {}", i)).collect()
+    }
+
+    #[test]
+    fn test_identical_trees_have_no_diff() {
+        let tree = MerkleTree::new(rows(37));
+
+        assert!(tree.diff(&tree.clone()).is_empty());
+        assert!(MerkleTree::new(Vec::new()).diff(&MerkleTree::new(Vec::new())).is_empty());
+    }
+
+    #[test]
+    fn test_diff_finds_changed_leaves() {
+        let tree = MerkleTree::new(rows(37));
+
+        let mut changed = rows(37);
+        changed[3] = "changed".to_string();
+        changed[20] = "changed".to_string();
+        changed[21] = "changed".to_string();
+        changed[36] = "changed".to_string();
+
+        let ranges = tree.diff(&MerkleTree::new(changed));
+        assert_eq!(ranges, vec![3..4, 20..22, 36..37]);
+    }
+
+    #[test]
+    fn test_diff_trees_of_different_sizes() {
+        let shorter = MerkleTree::new(rows(10));
+        let longer = MerkleTree::new(rows(13));
+
+        assert_eq!(shorter.diff(&longer), vec![10..13]);
+        assert_eq!(longer.diff(&shorter), vec![10..13]);
+
+        let mut edited = rows(13);
+        edited[9] = "changed".to_string();
+        assert_eq!(shorter.diff(&MerkleTree::new(edited)), vec![9..13]);
+
+        assert_eq!(MerkleTree::new(Vec::new()).diff(&shorter), vec![0..10]);
+    }
+
+    #[test]
+    fn test_diff_with_duplicated_last_leaf() {
+        // [a, b, c] and [a, b, c, c] share a root under the odd-node rule, but
+        // the extra leaf must still be reported.
+        let three = MerkleTree::new(rows(3));
+        let mut four_rows = rows(3);
+        four_rows.push("Row 2".to_string());
+        let four = MerkleTree::new(four_rows);
+
+        assert_eq!(three.root, four.root);
+        assert_eq!(three.diff(&four), vec![3..4]);
+    }
+
+    #[test]
+    fn test_diff_k_ary_trees() {
+        let builder = MerkleTreeBuilder::new().arity(4).unwrap();
+        let tree = builder.build(rows(37));
+
+        let mut changed = rows(40);
+        changed[5] = "changed".to_string();
+        changed[30] = "changed".to_string();
+
+        assert_eq!(tree.diff(&builder.build(changed)), vec![5..6, 30..31, 37..40]);
+        assert_eq!(tree.diff(&MerkleTree::new(rows(37))), vec![0..37]);
+    }
+}
@@ -0,0 +1,586 @@
+use crate::merkle::builder::MerkleTreeBuilder;
+use crate::merkle::error::IoError;
+use crate::merkle::mode::HashMode;
+use crate::merkle::node_store::NodeStore;
+use crate::merkle::tree::{default_arity, MerkleProof, MerkleTree, OddNodePolicy, ProofElement};
+use serde::Deserialize;
+use std::error::Error;
+use std::fmt;
+
+pub const ENCODING_VERSION: u8 = 1;
+pub const HASH_SIZE: usize = 64;
+pub const MAX_PROOF_DEPTH: usize = 64;
+
+// Proof layout: version (1) | arity (1) | leaf index (u64 BE) | depth (1)
+//               | leaf hash (64) | direction bitmap (ceil(siblings / 8))
+//               | siblings (depth * (arity - 1) * 64)
+const PROOF_HEADER_SIZE: usize = 1 + 1 + 8 + 1 + HASH_SIZE;
+
+// Tree layout: version (1) | arity (1) | odd node policy (1) | leaf count (u64 BE)
+//              | leaf hashes (count * 64)
+const TREE_HEADER_SIZE: usize = 1 + 1 + 1 + 8;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EncodingError {
+    UnsupportedVersion(u8),
+    UnsupportedArity(usize),
+    UnsupportedPolicy(u8),
+    UnsupportedHashMode(HashMode),
+    LengthMismatch { expected: usize, actual: usize },
+    DepthTooLarge { depth: usize, max: usize },
+    TooManyLeaves(u64),
+    NonCanonicalBitmap,
+    InvalidHash(String),
+    InvalidStore { level: usize },
+    Io(IoError),
+}
+
+impl fmt::Display for EncodingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EncodingError::UnsupportedVersion(version) => {
+                write!(f, "unsupported encoding version {}", version)
+            }
+            EncodingError::UnsupportedArity(arity) => write!(f, "unsupported arity {}", arity),
+            EncodingError::UnsupportedPolicy(policy) => write!(f, "unsupported odd node policy {}", policy),
+            EncodingError::UnsupportedHashMode(mode) => write!(f, "{:?} hashes have no binary encoding", mode),
+            EncodingError::LengthMismatch { expected, actual } => {
+                write!(f, "expected {} bytes, got {}", expected, actual)
+            }
+            EncodingError::DepthTooLarge { depth, max } => {
+                write!(f, "proof depth {} exceeds maximum of {}", depth, max)
+            }
+            EncodingError::TooManyLeaves(count) => write!(f, "leaf count {} is too large", count),
+            EncodingError::NonCanonicalBitmap => write!(f, "direction bitmap has padding bits set"),
+            EncodingError::InvalidHash(hash) => write!(f, "invalid {}-byte hex hash: {}", HASH_SIZE, hash),
+            EncodingError::InvalidStore { level } => {
+                write!(f, "stored nodes at level {} do not fit the tree shape", level)
+            }
+            EncodingError::Io(err) => write!(f, "node store failed: {}", err),
+        }
+    }
+}
+
+impl Error for EncodingError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            EncodingError::Io(err) => Some(err.get_ref()),
+            _ => None,
+        }
+    }
+}
+
+fn hash_to_bytes(hash: &str, out: &mut Vec<u8>) -> Result<(), EncodingError> {
+    match hex::decode(hash) {
+        Ok(bytes) if bytes.len() == HASH_SIZE => {
+            out.extend_from_slice(&bytes);
+            Ok(())
+        }
+        _ => Err(EncodingError::InvalidHash(hash.to_string())),
+    }
+}
+
+fn check_header(bytes: &[u8], header_size: usize) -> Result<(), EncodingError> {
+    if bytes.len() < header_size {
+        return Err(EncodingError::LengthMismatch {
+            expected: header_size,
+            actual: bytes.len(),
+        });
+    }
+
+    if bytes[0] != ENCODING_VERSION {
+        return Err(EncodingError::UnsupportedVersion(bytes[0]));
+    }
+
+    Ok(())
+}
+
+// The binary layouts only carry SHA512 hashes.
+fn check_mode(mode: HashMode) -> Result<(), EncodingError> {
+    match mode {
+        HashMode::Sha512 => Ok(()),
+        _ => Err(EncodingError::UnsupportedHashMode(mode)),
+    }
+}
+
+fn check_arity(arity: usize) -> Result<u8, EncodingError> {
+    match u8::try_from(arity) {
+        Ok(byte) if byte >= 2 => Ok(byte),
+        _ => Err(EncodingError::UnsupportedArity(arity)),
+    }
+}
+
+// Accepts exactly the shapes `MerkleTreeBuilder` does.
+fn builder_for(arity: usize, policy: OddNodePolicy) -> Result<MerkleTreeBuilder, EncodingError> {
+    check_arity(arity)?;
+
+    MerkleTreeBuilder::new()
+        .arity(arity)
+        .and_then(|builder| builder.odd_node_policy(policy))
+        .map_err(|_| EncodingError::UnsupportedArity(arity))
+}
+
+// Every stored node must be a well-formed hash for the mode, every level
+// must be exactly as long as the builder would have made it, every inner
+// node must be the hash of its children and the single top node must be the
+// root.
+fn check_store<S: NodeStore>(
+    root: &str,
+    arity: usize,
+    policy: OddNodePolicy,
+    mode: HashMode,
+    store: &S,
+) -> Result<(), EncodingError> {
+    let node = |level: usize, index: usize| {
+        store
+            .get(level, index)
+            .map_err(|err| EncodingError::Io(err.into()))?
+            .filter(|hash| mode.is_valid_hash(hash))
+            .ok_or(EncodingError::InvalidStore { level })
+    };
+
+    let mut children = (0..store.len(0)).map(|index| node(0, index)).collect::<Result<Vec<_>, _>>()?;
+    let mut padding = policy.padding_leaf(mode);
+
+    for level in 1..store.height() {
+        if children.len() < 2 || store.len(level) != children.len().div_ceil(arity) {
+            return Err(EncodingError::InvalidStore { level });
+        }
+
+        let mut parents = Vec::with_capacity(store.len(level));
+        for (index, group) in children.chunks(arity).enumerate() {
+            let hash = node(level, index)?;
+            if hash != MerkleTree::hash_group(group, arity, policy, padding.as_deref(), mode) {
+                return Err(EncodingError::InvalidStore { level });
+            }
+            parents.push(hash);
+        }
+
+        padding = OddNodePolicy::next_padding(padding, arity, mode);
+        children = parents;
+    }
+
+    let Some(top) = store.height().checked_sub(1) else {
+        if !root.is_empty() {
+            return Err(EncodingError::InvalidStore { level: 0 });
+        }
+        return Ok(());
+    };
+
+    if children.len() != 1 || children[0] != root {
+        return Err(EncodingError::InvalidStore { level: top });
+    }
+
+    Ok(())
+}
+
+#[derive(Deserialize)]
+pub(crate) struct MerkleTreeRepr<S> {
+    root: String,
+    #[serde(default = "default_arity")]
+    arity: usize,
+    #[serde(default)]
+    policy: OddNodePolicy,
+    #[serde(default)]
+    mode: HashMode,
+    #[serde(flatten)]
+    store: S,
+}
+
+impl<S: NodeStore> TryFrom<MerkleTreeRepr<S>> for MerkleTree<S> {
+    type Error = EncodingError;
+
+    fn try_from(repr: MerkleTreeRepr<S>) -> Result<Self, Self::Error> {
+        builder_for(repr.arity, repr.policy)?;
+        check_store(&repr.root, repr.arity, repr.policy, repr.mode, &repr.store)?;
+
+        Ok(MerkleTree {
+            root: repr.root,
+            arity: repr.arity,
+            policy: repr.policy,
+            mode: repr.mode,
+            store: repr.store,
+        })
+    }
+}
+
+fn policy_to_byte(policy: OddNodePolicy) -> u8 {
+    match policy {
+        OddNodePolicy::Duplicate => 0,
+        OddNodePolicy::Promote => 1,
+        OddNodePolicy::PadZero => 2,
+        OddNodePolicy::PadEmptyLeaf => 3,
+    }
+}
+
+fn policy_from_byte(byte: u8) -> Result<OddNodePolicy, EncodingError> {
+    match byte {
+        0 => Ok(OddNodePolicy::Duplicate),
+        1 => Ok(OddNodePolicy::Promote),
+        2 => Ok(OddNodePolicy::PadZero),
+        3 => Ok(OddNodePolicy::PadEmptyLeaf),
+        _ => Err(EncodingError::UnsupportedPolicy(byte)),
+    }
+}
+
+fn read_u64(bytes: &[u8]) -> u64 {
+    let mut buf = [0u8; 8];
+    buf.copy_from_slice(&bytes[..8]);
+    u64::from_be_bytes(buf)
+}
+
+impl MerkleProof {
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, EncodingError> {
+        check_mode(self.mode)?;
+        let arity = check_arity(self.arity)?;
+        let siblings = self.proof_path.len();
+        let depth = siblings.div_ceil(self.arity - 1);
+        if depth > MAX_PROOF_DEPTH {
+            return Err(EncodingError::DepthTooLarge { depth, max: MAX_PROOF_DEPTH });
+        }
+
+        // Every level carries exactly `arity - 1` siblings.
+        if depth * (self.arity - 1) != siblings {
+            return Err(EncodingError::LengthMismatch {
+                expected: depth * (self.arity - 1),
+                actual: siblings,
+            });
+        }
+
+        let mut bytes = Vec::with_capacity(PROOF_HEADER_SIZE + siblings.div_ceil(8) + siblings * HASH_SIZE);
+        bytes.push(ENCODING_VERSION);
+        bytes.push(arity);
+        bytes.extend_from_slice(&(self.leaf_index as u64).to_be_bytes());
+        bytes.push(depth as u8);
+        hash_to_bytes(&self.leaf_hash, &mut bytes)?;
+
+        let mut bitmap = vec![0u8; siblings.div_ceil(8)];
+        for (i, element) in self.proof_path.iter().enumerate() {
+            if element.is_right {
+                bitmap[i / 8] |= 1 << (i % 8);
+            }
+        }
+        bytes.extend_from_slice(&bitmap);
+
+        for element in &self.proof_path {
+            hash_to_bytes(&element.hash, &mut bytes)?;
+        }
+
+        Ok(bytes)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, EncodingError> {
+        check_header(bytes, PROOF_HEADER_SIZE)?;
+
+        let arity = bytes[1] as usize;
+        check_arity(arity)?;
+
+        let leaf_index = read_u64(&bytes[2..]);
+        let leaf_index = usize::try_from(leaf_index).map_err(|_| EncodingError::TooManyLeaves(leaf_index))?;
+
+        let depth = bytes[10] as usize;
+        if depth > MAX_PROOF_DEPTH {
+            return Err(EncodingError::DepthTooLarge { depth, max: MAX_PROOF_DEPTH });
+        }
+
+        let siblings = depth * (arity - 1);
+        let bitmap_len = siblings.div_ceil(8);
+        let expected = PROOF_HEADER_SIZE + bitmap_len + siblings * HASH_SIZE;
+        if bytes.len() != expected {
+            return Err(EncodingError::LengthMismatch {
+                expected,
+                actual: bytes.len(),
+            });
+        }
+
+        let leaf_hash = hex::encode(&bytes[11..PROOF_HEADER_SIZE]);
+        let bitmap = &bytes[PROOF_HEADER_SIZE..PROOF_HEADER_SIZE + bitmap_len];
+
+        if !siblings.is_multiple_of(8) && bitmap[bitmap_len - 1] >> (siblings % 8) != 0 {
+            return Err(EncodingError::NonCanonicalBitmap);
+        }
+
+        let proof_path = bytes[PROOF_HEADER_SIZE + bitmap_len..]
+            .chunks_exact(HASH_SIZE)
+            .enumerate()
+            .map(|(i, hash)| ProofElement {
+                hash: hex::encode(hash),
+                is_right: bitmap[i / 8] & (1 << (i % 8)) != 0,
+            })
+            .collect();
+
+        Ok(MerkleProof {
+            leaf_index,
+            leaf_hash,
+            arity,
+            mode: HashMode::Sha512,
+            proof_path,
+        })
+    }
+}
+
+impl<S: NodeStore> MerkleTree<S> {
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, EncodingError> {
+        check_mode(self.mode)?;
+        let arity = check_arity(self.arity)?;
+        let leaf_count = self.leaf_count();
+
+        let mut bytes = Vec::with_capacity(TREE_HEADER_SIZE + leaf_count * HASH_SIZE);
+        bytes.push(ENCODING_VERSION);
+        bytes.push(arity);
+        bytes.push(policy_to_byte(self.policy));
+        bytes.extend_from_slice(&(leaf_count as u64).to_be_bytes());
+
+        for i in 0..leaf_count {
+            let leaf = self
+                .store
+                .get(0, i)
+                .map_err(|err| EncodingError::Io(err.into()))?
+                .unwrap_or_default();
+            hash_to_bytes(&leaf, &mut bytes)?;
+        }
+
+        Ok(bytes)
+    }
+}
+
+impl MerkleTree {
+
+    // Only leaf hashes are encoded; inner levels are recomputed so a decoded
+    // tree is always internally consistent.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, EncodingError> {
+        check_header(bytes, TREE_HEADER_SIZE)?;
+
+        let arity = bytes[1] as usize;
+        let policy = policy_from_byte(bytes[2])?;
+        let builder = builder_for(arity, policy)?;
+
+        let leaf_count = read_u64(&bytes[3..]);
+        let expected = usize::try_from(leaf_count)
+            .ok()
+            .and_then(|count| count.checked_mul(HASH_SIZE))
+            .and_then(|size| size.checked_add(TREE_HEADER_SIZE))
+            .ok_or(EncodingError::TooManyLeaves(leaf_count))?;
+
+        if bytes.len() != expected {
+            return Err(EncodingError::LengthMismatch {
+                expected,
+                actual: bytes.len(),
+            });
+        }
+
+        let leaf_hashes = bytes[TREE_HEADER_SIZE..]
+            .chunks_exact(HASH_SIZE)
+            .map(hex::encode)
+            .collect();
+
+        Ok(builder.build_from_leaf_hashes(leaf_hashes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_tree() -> MerkleTree {
+        MerkleTree::new((0..11).map(|i| format!("Tx {}", i)).collect())
+    }
+
+    #[test]
+    fn test_proof_json_roundtrip() {
+        let tree = sample_tree();
+        let proof = tree.get_proof(7).unwrap();
+
+        let json = serde_json::to_string(&proof).expect("Proof should serialize");
+        let decoded: MerkleProof = serde_json::from_str(&json).expect("Proof should deserialize");
+
+        assert_eq!(decoded, proof);
+        assert!(json.contains(&proof.leaf_hash), "JSON should carry the full leaf hash");
+    }
+
+    #[test]
+    fn test_decoded_proof_displays() {
+        let mut json = serde_json::to_value(sample_tree().get_proof(3).unwrap()).unwrap();
+
+        for leaf_hash in ["ab", "ééééééééééééé"] {
+            json["leaf_hash"] = leaf_hash.into();
+            let proof: MerkleProof = serde_json::from_value(json.clone()).unwrap();
+            assert!(proof.to_string().contains(&leaf_hash[..2]));
+        }
+    }
+
+    #[test]
+    fn test_tree_json_roundtrip() {
+        let tree = sample_tree();
+
+        let json = serde_json::to_string(&tree).expect("Tree should serialize");
+        let decoded: MerkleTree = serde_json::from_str(&json).expect("Tree should deserialize");
+
+        assert_eq!(decoded, tree);
+    }
+
+    #[test]
+    fn test_malformed_tree_json_rejected() {
+        let tree = sample_tree();
+        let decode = |json: serde_json::Value| serde_json::from_value::<MerkleTree>(json).map_err(|err| err.to_string());
+        let json = serde_json::to_value(&tree).unwrap();
+
+        for arity in [0, 1] {
+            let mut unary = json.clone();
+            unary["arity"] = arity.into();
+            assert_eq!(decode(unary), Err(EncodingError::UnsupportedArity(arity).to_string()));
+        }
+
+        let mut promoted = serde_json::to_value(MerkleTreeBuilder::new().arity(4).unwrap().build(vec!["a".to_string()])).unwrap();
+        promoted["policy"] = "Promote".into();
+        assert_eq!(decode(promoted), Err(EncodingError::UnsupportedArity(4).to_string()));
+
+        let mut short = json.clone();
+        short["levels"][1].as_array_mut().unwrap().pop();
+        assert_eq!(decode(short), Err(EncodingError::InvalidStore { level: 1 }.to_string()));
+
+        let mut rerooted = json.clone();
+        rerooted["root"] = "0".repeat(128).into();
+        assert_eq!(decode(rerooted), Err(EncodingError::InvalidStore { level: 4 }.to_string()));
+
+        // An inner node that is not the hash of its children.
+        let mut rehashed = json.clone();
+        rehashed["levels"][2][1] = "0".repeat(128).into();
+        assert_eq!(decode(rehashed), Err(EncodingError::InvalidStore { level: 2 }.to_string()));
+
+        let mut stacked = json;
+        let root = stacked["root"].clone();
+        stacked["levels"].as_array_mut().unwrap().push(vec![root].into());
+        assert_eq!(decode(stacked), Err(EncodingError::InvalidStore { level: 5 }.to_string()));
+    }
+
+    #[test]
+    fn test_malformed_node_hashes_rejected() {
+        let tree = MerkleTreeBuilder::new()
+            .hash_mode(HashMode::Bitcoin)
+            .build_from_leaf_hashes(vec!["ab".repeat(32), "cd".repeat(32), "ef".repeat(32)]);
+        let json = serde_json::to_value(&tree).unwrap();
+        let decode = |json: serde_json::Value| serde_json::from_value::<MerkleTree>(json).map_err(|err| err.to_string());
+
+        assert_eq!(decode(json.clone()).as_ref(), Ok(&tree));
+
+        for bad in ["zz".repeat(32), "AB".repeat(32), "ab".repeat(64)] {
+            let mut malformed = json.clone();
+            malformed["levels"][0][1] = bad.into();
+            assert_eq!(decode(malformed), Err(EncodingError::InvalidStore { level: 0 }.to_string()));
+        }
+    }
+
+    #[test]
+    fn test_proof_binary_roundtrip() {
+        let tree = sample_tree();
+
+        for i in 0..tree.leaf_count() {
+            let proof = tree.get_proof(i).unwrap();
+            let bytes = proof.to_bytes().expect("Proof should encode");
+
+            assert_eq!(bytes.len(), PROOF_HEADER_SIZE + 1 + proof.proof_path.len() * HASH_SIZE);
+
+            let decoded = MerkleProof::from_bytes(&bytes).expect("Proof should decode");
+            assert_eq!(decoded, proof);
+            assert!(tree.verify_proof(&decoded).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_tree_binary_roundtrip() {
+        let tree = sample_tree();
+        let bytes = tree.to_bytes().expect("Tree should encode");
+
+        assert_eq!(MerkleTree::from_bytes(&bytes), Ok(tree));
+
+        let empty = MerkleTree::new(Vec::new());
+        assert_eq!(MerkleTree::from_bytes(&empty.to_bytes().unwrap()), Ok(empty));
+    }
+
+    #[test]
+    fn test_malformed_proof_rejected() {
+        let proof = sample_tree().get_proof(3).unwrap();
+        let bytes = proof.to_bytes().unwrap();
+
+        assert!(matches!(
+            MerkleProof::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(EncodingError::LengthMismatch { .. })
+        ));
+
+        let mut extended = bytes.clone();
+        extended.push(0);
+        assert!(matches!(
+            MerkleProof::from_bytes(&extended),
+            Err(EncodingError::LengthMismatch { .. })
+        ));
+
+        let mut wrong_version = bytes.clone();
+        wrong_version[0] = 2;
+        assert_eq!(MerkleProof::from_bytes(&wrong_version), Err(EncodingError::UnsupportedVersion(2)));
+
+        let mut unary = bytes.clone();
+        unary[1] = 1;
+        assert_eq!(MerkleProof::from_bytes(&unary), Err(EncodingError::UnsupportedArity(1)));
+
+        let mut too_deep = bytes.clone();
+        too_deep[10] = 200;
+        assert_eq!(
+            MerkleProof::from_bytes(&too_deep),
+            Err(EncodingError::DepthTooLarge { depth: 200, max: MAX_PROOF_DEPTH })
+        );
+
+        let mut padded = bytes;
+        padded[PROOF_HEADER_SIZE] |= 0x80;
+        assert_eq!(MerkleProof::from_bytes(&padded), Err(EncodingError::NonCanonicalBitmap));
+
+        assert!(MerkleProof::from_bytes(&[]).is_err());
+    }
+
+    #[test]
+    fn test_wide_tree_binary_roundtrip() {
+        let tree = MerkleTreeBuilder::new()
+            .arity(4)
+            .unwrap()
+            .build((0..11).map(|i| format!("Tx {}", i)).collect());
+
+        let proof = tree.get_proof(9).unwrap();
+        let bytes = proof.to_bytes().expect("Proof should encode");
+
+        assert_eq!(bytes[1], 4);
+        assert_eq!(bytes[10] as usize, tree.height() - 1);
+        assert_eq!(MerkleProof::from_bytes(&bytes), Ok(proof));
+
+        assert_eq!(MerkleTree::from_bytes(&tree.to_bytes().unwrap()), Ok(tree));
+    }
+
+    #[test]
+    fn test_odd_node_policy_roundtrip() {
+        for policy in [OddNodePolicy::Promote, OddNodePolicy::PadZero, OddNodePolicy::PadEmptyLeaf] {
+            let tree = MerkleTreeBuilder::new()
+                .odd_node_policy(policy)
+                .unwrap()
+                .build((0..11).map(|i| format!("Tx {}", i)).collect());
+
+            let mut bytes = tree.to_bytes().unwrap();
+            assert_eq!(MerkleTree::from_bytes(&bytes), Ok(tree));
+
+            bytes[2] = 9;
+            assert_eq!(MerkleTree::from_bytes(&bytes), Err(EncodingError::UnsupportedPolicy(9)));
+        }
+    }
+
+    #[test]
+    fn test_malformed_tree_rejected() {
+        let mut bytes = vec![ENCODING_VERSION, 2, 0];
+        bytes.extend_from_slice(&u64::MAX.to_be_bytes());
+
+        assert_eq!(MerkleTree::from_bytes(&bytes), Err(EncodingError::TooManyLeaves(u64::MAX)));
+
+        let mut proof = sample_tree().get_proof(0).unwrap();
+        proof.leaf_hash = "not hex".to_string();
+        assert!(matches!(proof.to_bytes(), Err(EncodingError::InvalidHash(_))));
+    }
+}
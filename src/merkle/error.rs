@@ -0,0 +1,197 @@
+use crate::merkle::mode::HashMode;
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::sync::Arc;
+
+// Keeps the io::Error behind an Arc so the error enums stay Clone and
+// comparable. Two errors are equal when their kind and message match.
+#[derive(Debug, Clone)]
+pub struct IoError(Arc<io::Error>);
+
+impl IoError {
+    pub fn kind(&self) -> io::ErrorKind {
+        self.0.kind()
+    }
+
+    pub fn get_ref(&self) -> &io::Error {
+        &self.0
+    }
+}
+
+impl From<io::Error> for IoError {
+    fn from(err: io::Error) -> Self {
+        IoError(Arc::new(err))
+    }
+}
+
+impl PartialEq for IoError {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind() == other.kind() && self.0.to_string() == other.0.to_string()
+    }
+}
+
+impl Eq for IoError {}
+
+impl fmt::Display for IoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MerkleError {
+    EmptyTree,
+    IndexOutOfRange { index: usize, leaf_count: usize },
+    MissingNode { level: usize, index: usize },
+    KeyNotFound(String),
+    KeyPresent(String),
+    InvalidHash(String),
+    SumOverflow { level: usize, index: usize },
+    UnsupportedShape,
+    EmptySelection,
+    InvalidRange { start: usize, end: usize, leaf_count: usize },
+    InvalidTreeSize { size: usize, leaf_count: usize },
+    NotALeaf { position: usize },
+    PositionOutOfRange { position: usize, size: usize },
+    Io(IoError),
+}
+
+impl fmt::Display for MerkleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MerkleError::EmptyTree => write!(f, "tree has no leaves"),
+            MerkleError::IndexOutOfRange { index, leaf_count } => {
+                write!(f, "leaf index {} out of range for {} leaves", index, leaf_count)
+            }
+            MerkleError::MissingNode { level, index } => {
+                write!(f, "node {} at level {} is missing from the store", index, level)
+            }
+            MerkleError::KeyNotFound(key) => write!(f, "key {:?} is not in the tree", key),
+            MerkleError::KeyPresent(key) => write!(f, "key {:?} is in the tree", key),
+            MerkleError::InvalidHash(hash) => write!(f, "invalid hash {:?}", hash),
+            MerkleError::SumOverflow { level, index } => {
+                write!(f, "sum of node {} at level {} overflows", index, level)
+            }
+            MerkleError::UnsupportedShape => write!(f, "operation is not supported for this tree shape"),
+            MerkleError::EmptySelection => write!(f, "no leaves selected"),
+            MerkleError::InvalidRange { start, end, leaf_count } => {
+                write!(f, "range {}..{} is empty or out of range for {} leaves", start, end, leaf_count)
+            }
+            MerkleError::InvalidTreeSize { size, leaf_count } => {
+                write!(f, "tree size {} is not within 1..={}", size, leaf_count)
+            }
+            MerkleError::NotALeaf { position } => write!(f, "node at position {} is not a leaf", position),
+            MerkleError::PositionOutOfRange { position, size } => {
+                write!(f, "position {} out of range for {} nodes", position, size)
+            }
+            MerkleError::Io(err) => write!(f, "node store failed: {}", err),
+        }
+    }
+}
+
+impl Error for MerkleError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            MerkleError::Io(err) => Some(err.get_ref()),
+            _ => None,
+        }
+    }
+}
+
+// Depths are counted in tree levels, not in sibling hashes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProofError {
+    IndexOutOfRange { index: usize, leaf_count: usize },
+    ArityMismatch { expected: usize, actual: usize },
+    HashModeMismatch { expected: HashMode, actual: HashMode },
+    DepthMismatch { expected: usize, actual: usize },
+    LeafMismatch { expected: String, actual: String },
+    RootMismatch { expected: String, actual: String },
+    PathMismatch { index: usize, leaf_count: usize },
+    InvalidTreeSizes { first: usize, second: usize },
+    ConsistencyPathMismatch { first: usize, second: usize },
+    InvalidHash(String),
+    SumOverflow { level: usize },
+    SumMismatch { expected: u64, actual: u64 },
+    InvalidLeafIndices,
+    LeafCountMismatch { expected: usize, actual: usize },
+    HashCountMismatch { expected: usize, actual: usize },
+    InvalidRange { start: usize, end: usize, leaf_count: usize },
+    InvalidNeighbours,
+    KeyNotExcluded(String),
+    EntryMismatch(String),
+    InvalidMmrSize(usize),
+    PositionOutOfRange { position: usize, size: usize },
+    MmrPathMismatch { position: usize, size: usize },
+    Io(IoError),
+}
+
+impl fmt::Display for ProofError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProofError::IndexOutOfRange { index, leaf_count } => {
+                write!(f, "proof for leaf {} but the tree has {} leaves", index, leaf_count)
+            }
+            ProofError::ArityMismatch { expected, actual } => {
+                write!(f, "expected a proof for arity {}, got {}", expected, actual)
+            }
+            ProofError::HashModeMismatch { expected, actual } => {
+                write!(f, "expected a {:?} proof, got {:?}", expected, actual)
+            }
+            ProofError::DepthMismatch { expected, actual } => {
+                write!(f, "expected proof depth {}, got {}", expected, actual)
+            }
+            ProofError::LeafMismatch { expected, actual } => {
+                write!(f, "leaf hash mismatch: expected {}, got {}", expected, actual)
+            }
+            ProofError::RootMismatch { expected, actual } => {
+                write!(f, "root mismatch: expected {}, computed {}", expected, actual)
+            }
+            ProofError::PathMismatch { index, leaf_count } => {
+                write!(f, "proof path does not fit leaf {} of {}", index, leaf_count)
+            }
+            ProofError::InvalidTreeSizes { first, second } => {
+                write!(f, "cannot prove consistency from tree size {} to {}", first, second)
+            }
+            ProofError::ConsistencyPathMismatch { first, second } => {
+                write!(f, "consistency path does not fit tree sizes {} and {}", first, second)
+            }
+            ProofError::InvalidHash(hash) => write!(f, "invalid hash {:?} in proof", hash),
+            ProofError::SumOverflow { level } => write!(f, "sum overflows at proof level {}", level),
+            ProofError::SumMismatch { expected, actual } => {
+                write!(f, "sum mismatch: expected total {}, computed {}", expected, actual)
+            }
+            ProofError::InvalidLeafIndices => write!(f, "leaf indices are empty or not strictly increasing"),
+            ProofError::LeafCountMismatch { expected, actual } => {
+                write!(f, "expected {} leaves, got {}", expected, actual)
+            }
+            ProofError::HashCountMismatch { expected, actual } => {
+                write!(f, "proof needs {} hashes, got {}", expected, actual)
+            }
+            ProofError::InvalidRange { start, end, leaf_count } => {
+                write!(f, "range {}..{} is empty or out of range for {} leaves", start, end, leaf_count)
+            }
+            ProofError::InvalidNeighbours => write!(f, "neighbours are not adjacent leaves of the tree"),
+            ProofError::KeyNotExcluded(key) => write!(f, "key {:?} is not between the neighbours", key),
+            ProofError::EntryMismatch(name) => write!(f, "entry {:?} does not match the path", name),
+            ProofError::InvalidMmrSize(size) => write!(f, "{} nodes is not the size of a mountain range", size),
+            ProofError::PositionOutOfRange { position, size } => {
+                write!(f, "proof for position {} but the range has {} nodes", position, size)
+            }
+            ProofError::MmrPathMismatch { position, size } => {
+                write!(f, "proof path does not fit position {} of {} nodes", position, size)
+            }
+            ProofError::Io(err) => write!(f, "node store failed: {}", err),
+        }
+    }
+}
+
+impl Error for ProofError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ProofError::Io(err) => Some(err.get_ref()),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,499 @@
+use crate::merkle::chunking::{ChunkedFile, ChunkerConfig};
+use crate::merkle::error::ProofError;
+use crate::merkle::mode::HashMode;
+use crate::merkle::tree::{MerkleProof, MerkleTree};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::{self, File, Metadata};
+use std::io::{self, BufReader};
+use std::path::{Component, Path, PathBuf};
+use std::sync::OnceLock;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+    File,
+    Dir,
+    Symlink,
+}
+
+impl EntryKind {
+    fn tag(&self) -> &'static str {
+        match self {
+            EntryKind::File => "file",
+            EntryKind::Dir => "dir",
+            EntryKind::Symlink => "link",
+        }
+    }
+}
+
+// `hash` is the chunk tree root of a file's contents, the SHA512 of a
+// symlink's target, or the root of a directory's own tree. `size` counts
+// bytes for files and links and entries for directories.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub name: String,
+    pub kind: EntryKind,
+    pub mode: u32,
+    pub size: u64,
+    pub hash: String,
+}
+
+impl Entry {
+
+    // The name goes last so it can hold spaces without making the record
+    // ambiguous.
+    pub fn record(&self) -> String {
+        format!("{} {:o} {} {} {}", self.kind.tag(), self.mode, self.size, self.hash, self.name)
+    }
+
+    pub fn leaf_hash(&self) -> String {
+        MerkleTree::hash_leaf(self.record().as_bytes())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DirTree {
+    pub entries: Vec<Entry>,
+    pub subdirs: BTreeMap<String, DirTree>,
+    pub tree: MerkleTree,
+}
+
+#[derive(Debug, Clone)]
+pub struct PathProof {
+    pub steps: Vec<(Entry, MerkleProof)>,
+}
+
+// FIFOs, sockets and devices have no contents to hash, and reading one may
+// block or never end. `hash_dir` rejects them with this as the source of an
+// `Unsupported` io error.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpecialFileError {
+    pub path: PathBuf,
+}
+
+impl fmt::Display for SpecialFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} is not a regular file, directory or symlink", self.path.display())
+    }
+}
+
+impl std::error::Error for SpecialFileError {}
+
+pub fn hash_dir<P: AsRef<Path>>(path: P) -> io::Result<DirTree> {
+    let mut entries = Vec::new();
+    let mut subdirs = BTreeMap::new();
+
+    for dir_entry in fs::read_dir(path)? {
+        let dir_entry = dir_entry?;
+        let name = dir_entry.file_name().into_string().map_err(|name| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("non UTF-8 file name {:?}", name))
+        })?;
+
+        let path = dir_entry.path();
+        let metadata = fs::symlink_metadata(&path)?;
+        let mode = mode_of(&metadata);
+
+        let file_type = metadata.file_type();
+
+        let entry = if file_type.is_symlink() {
+            // The raw bytes keep distinct non UTF-8 targets apart.
+            let target = fs::read_link(&path)?;
+            let target = target.as_os_str().as_encoded_bytes();
+            Entry {
+                name,
+                kind: EntryKind::Symlink,
+                mode,
+                size: target.len() as u64,
+                hash: MerkleTree::hash_leaf(target),
+            }
+        } else if file_type.is_dir() {
+            let subdir = hash_dir(&path)?;
+            let entry = Entry {
+                name: name.clone(),
+                kind: EntryKind::Dir,
+                mode,
+                size: subdir.entries.len() as u64,
+                hash: subdir.root().to_string(),
+            };
+            subdirs.insert(name, subdir);
+            entry
+        } else if file_type.is_file() {
+            let chunked = ChunkedFile::from_reader(BufReader::new(File::open(&path)?), ChunkerConfig::default())?;
+            Entry {
+                name,
+                kind: EntryKind::File,
+                mode,
+                size: chunked.chunks.iter().map(|chunk| chunk.len as u64).sum(),
+                hash: file_hash(&chunked),
+            }
+        } else {
+            return Err(io::Error::new(io::ErrorKind::Unsupported, SpecialFileError { path }));
+        };
+
+        entries.push(entry);
+    }
+
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+    let tree = MerkleTree::from_leaf_hashes(entries.iter().map(Entry::leaf_hash).collect());
+
+    Ok(DirTree { entries, subdirs, tree })
+}
+
+// Files are hashed chunk by chunk so large files are never held in memory.
+// An empty file has no chunks, so it takes the leaf hash of no bytes rather
+// than an empty root.
+fn file_hash(chunked: &ChunkedFile) -> String {
+    if chunked.is_empty() {
+        MerkleTree::hash_leaf(b"")
+    } else {
+        chunked.root().to_string()
+    }
+}
+
+// The root of a directory with no entries. Any SHA512 output is the root of
+// some file, so empty directories take the all-zero hash, which no file
+// root can realistically take, rather than an empty root.
+fn empty_dir_root() -> &'static str {
+    static EMPTY_DIR_ROOT: OnceLock<String> = OnceLock::new();
+
+    EMPTY_DIR_ROOT.get_or_init(|| HashMode::Sha512.zero_hash())
+}
+
+pub fn diff_dirs<P: AsRef<Path>, Q: AsRef<Path>>(a: P, b: Q) -> io::Result<Vec<PathBuf>> {
+    Ok(hash_dir(a)?.diff(&hash_dir(b)?))
+}
+
+#[cfg(unix)]
+fn mode_of(metadata: &Metadata) -> u32 {
+    use std::os::unix::fs::PermissionsExt;
+    metadata.permissions().mode() & 0o7777
+}
+
+#[cfg(not(unix))]
+fn mode_of(metadata: &Metadata) -> u32 {
+    match (metadata.is_dir(), metadata.permissions().readonly()) {
+        (true, _) => 0o755,
+        (false, true) => 0o444,
+        (false, false) => 0o644,
+    }
+}
+
+impl DirTree {
+
+    pub fn root(&self) -> &str {
+        if self.entries.is_empty() {
+            empty_dir_root()
+        } else {
+            &self.tree.root
+        }
+    }
+
+    // Lists every path whose entry differs, descending only into directories
+    // present on both sides with different contents.
+    pub fn diff(&self, other: &DirTree) -> Vec<PathBuf> {
+        let mut changed = Vec::new();
+        self.diff_into(other, Path::new(""), &mut changed);
+        changed
+    }
+
+    fn diff_into(&self, other: &DirTree, prefix: &Path, changed: &mut Vec<PathBuf>) {
+        if self.root() == other.root() {
+            return;
+        }
+
+        let ours: BTreeMap<&str, &Entry> = self.entries.iter().map(|e| (e.name.as_str(), e)).collect();
+        let theirs: BTreeMap<&str, &Entry> = other.entries.iter().map(|e| (e.name.as_str(), e)).collect();
+
+        let mut names: Vec<&str> = ours.keys().chain(theirs.keys()).copied().collect();
+        names.sort_unstable();
+        names.dedup();
+
+        for name in names {
+            match (ours.get(name), theirs.get(name)) {
+                (Some(a), Some(b)) if a == b => {}
+                (Some(a), Some(b)) if a.kind == EntryKind::Dir && b.kind == EntryKind::Dir => {
+                    // A mode change on the directory itself is reported as well
+                    // as whatever changed inside it.
+                    if a.mode != b.mode {
+                        changed.push(prefix.join(name));
+                    }
+                    self.subdirs[name].diff_into(&other.subdirs[name], &prefix.join(name), changed);
+                }
+                _ => changed.push(prefix.join(name)),
+            }
+        }
+    }
+
+    pub fn prove<P: AsRef<Path>>(&self, path: P) -> Option<PathProof> {
+        let mut steps = Vec::new();
+        let mut dir = self;
+        let mut components = path.as_ref().components().peekable();
+
+        while let Some(component) = components.next() {
+            let name = match component {
+                Component::Normal(name) => name.to_str()?,
+                _ => return None,
+            };
+
+            let index = dir.entries.binary_search_by(|e| e.name.as_str().cmp(name)).ok()?;
+            steps.push((dir.entries[index].clone(), dir.tree.get_proof(index).ok()?));
+
+            if components.peek().is_some() {
+                dir = dir.subdirs.get(name)?;
+            }
+        }
+
+        (!steps.is_empty()).then_some(PathProof { steps })
+    }
+}
+
+impl PathProof {
+
+    // Checks the chain of entries from `root` down to a regular file at
+    // `path` and that the file holds exactly `contents`.
+    pub fn verify<P: AsRef<Path>>(&self, root: &str, path: P, contents: &[u8]) -> Result<(), ProofError> {
+        let names: Vec<_> = path.as_ref().components().collect();
+        if names.len() != self.steps.len() {
+            return Err(ProofError::DepthMismatch {
+                expected: names.len(),
+                actual: self.steps.len(),
+            });
+        }
+
+        let mut expected_root = root;
+
+        for (i, ((entry, proof), component)) in self.steps.iter().zip(&names).enumerate() {
+            let is_last = i + 1 == self.steps.len();
+            let expected_kind = if is_last { EntryKind::File } else { EntryKind::Dir };
+
+            if *component != Component::Normal(entry.name.as_ref()) || entry.kind != expected_kind {
+                return Err(ProofError::EntryMismatch(entry.name.clone()));
+            }
+
+            let leaf_hash = entry.leaf_hash();
+            if proof.leaf_hash != leaf_hash {
+                return Err(ProofError::LeafMismatch {
+                    expected: leaf_hash,
+                    actual: proof.leaf_hash.clone(),
+                });
+            }
+
+            proof.verify(expected_root)?;
+            expected_root = &entry.hash;
+        }
+
+        let (file, _) = &self.steps[self.steps.len() - 1];
+        let hash = file_hash(&ChunkedFile::from_bytes(contents, ChunkerConfig::default()));
+        if file.size != contents.len() as u64 || file.hash != hash {
+            return Err(ProofError::LeafMismatch {
+                expected: file.hash.clone(),
+                actual: hash,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("merkle-fs-{}-{}", std::process::id(), name));
+        fs::remove_dir_all(&dir).ok();
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write(root: &Path, path: &str, contents: &str) {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    fn sample_release(name: &str) -> PathBuf {
+        let dir = scratch_dir(name);
+        write(&dir, "bin/server", "server binary");
+        write(&dir, "bin/worker", "worker binary");
+        write(&dir, "config/app.toml", "port = 8080");
+        write(&dir, "README", "release notes");
+        dir
+    }
+
+    #[test]
+    fn test_hash_is_deterministic() {
+        let a = sample_release("deterministic-a");
+
+        // The same tree written in a different order hashes the same.
+        let b = scratch_dir("deterministic-b");
+        write(&b, "README", "release notes");
+        write(&b, "config/app.toml", "port = 8080");
+        write(&b, "bin/worker", "worker binary");
+        write(&b, "bin/server", "server binary");
+
+        let tree = hash_dir(&a).expect("Should hash directory");
+        assert_eq!(tree.root(), hash_dir(&b).unwrap().root());
+
+        let names: Vec<&str> = tree.entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["README", "bin", "config"]);
+        assert_eq!(tree.entries[1].kind, EntryKind::Dir);
+        assert_eq!(tree.entries[1].hash, tree.subdirs["bin"].root());
+
+        fs::remove_dir_all(a).ok();
+        fs::remove_dir_all(b).ok();
+    }
+
+    #[test]
+    fn test_diff_dirs_lists_changed_paths() {
+        let a = sample_release("diff-a");
+        let b = sample_release("diff-b");
+
+        assert!(diff_dirs(&a, &b).unwrap().is_empty());
+
+        write(&b, "bin/worker", "patched worker binary");
+        write(&b, "config/extra.toml", "debug = true");
+        fs::remove_file(b.join("README")).unwrap();
+
+        assert_eq!(
+            diff_dirs(&a, &b).unwrap(),
+            vec![
+                PathBuf::from("README"),
+                PathBuf::from("bin/worker"),
+                PathBuf::from("config/extra.toml"),
+            ]
+        );
+
+        fs::remove_dir_all(a).ok();
+        fs::remove_dir_all(b).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_mode_is_part_of_the_root() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let a = sample_release("mode-a");
+        let before = hash_dir(&a).unwrap();
+
+        fs::set_permissions(a.join("bin/server"), fs::Permissions::from_mode(0o755)).unwrap();
+        fs::set_permissions(a.join("bin/worker"), fs::Permissions::from_mode(0o600)).unwrap();
+        let after = hash_dir(&a).unwrap();
+
+        assert_ne!(before.root(), after.root());
+        assert_eq!(before.diff(&after), vec![PathBuf::from("bin/server"), PathBuf::from("bin/worker")]);
+
+        fs::remove_dir_all(a).ok();
+    }
+
+    #[test]
+    fn test_file_inclusion_proofs() {
+        let dir = sample_release("proofs");
+        let tree = hash_dir(&dir).unwrap();
+
+        for (path, contents) in [("README", "release notes"), ("bin/worker", "worker binary"), ("config/app.toml", "port = 8080")] {
+            let proof = tree.prove(path).expect("Should prove file");
+
+            assert_eq!(proof.verify(tree.root(), path, contents.as_bytes()), Ok(()), "Proof for {} failed", path);
+            assert!(matches!(proof.verify(tree.root(), path, b"tampered"), Err(ProofError::LeafMismatch { .. })));
+        }
+
+        let proof = tree.prove("bin/server").unwrap();
+        assert_eq!(
+            proof.verify(tree.root(), "bin/worker", b"server binary"),
+            Err(ProofError::EntryMismatch("server".to_string()))
+        );
+        assert!(matches!(
+            proof.verify(&"0".repeat(128), "bin/server", b"server binary"),
+            Err(ProofError::RootMismatch { .. })
+        ));
+        assert_eq!(
+            proof.verify(tree.root(), "server", b"server binary"),
+            Err(ProofError::DepthMismatch { expected: 1, actual: 2 })
+        );
+
+        assert!(tree.prove("bin/missing").is_none());
+        assert!(tree.prove("").is_none());
+
+        // Directories are not files, so their proofs never verify as one.
+        let proof = tree.prove("bin").unwrap();
+        assert_eq!(proof.verify(tree.root(), "bin", b""), Err(ProofError::EntryMismatch("bin".to_string())));
+
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn test_large_and_empty_files_prove() {
+        let dir = scratch_dir("chunked");
+        let large: String = (0..20_000).map(|i| format!("line {}\n", i)).collect();
+        write(&dir, "large.log", &large);
+        write(&dir, "empty", "");
+
+        let tree = hash_dir(&dir).unwrap();
+        assert_eq!(tree.entries[1].size, large.len() as u64);
+        assert_eq!(tree.entries[1].hash, ChunkedFile::from_bytes(large.as_bytes(), ChunkerConfig::default()).root());
+
+        for (path, contents) in [("large.log", large.as_str()), ("empty", "")] {
+            let proof = tree.prove(path).unwrap();
+            assert_eq!(proof.verify(tree.root(), path, contents.as_bytes()), Ok(()), "Proof for {} failed", path);
+        }
+
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn test_empty_dirs_have_their_own_root() {
+        let dir = scratch_dir("empty");
+        let empty = hash_dir(&dir).unwrap();
+
+        assert_ne!(empty.root(), MerkleTree::new(Vec::new()).root);
+        assert_eq!(empty.root(), "0".repeat(128));
+
+        fs::create_dir(dir.join("nested")).unwrap();
+        let nested = hash_dir(&dir).unwrap();
+        assert_eq!(nested.entries[0].hash, empty.root());
+        assert_ne!(nested.root(), empty.root());
+
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_special_files_rejected() {
+        use std::os::unix::net::UnixListener;
+
+        let dir = sample_release("special");
+        let socket = dir.join("bin/control.sock");
+        let _listener = UnixListener::bind(&socket).unwrap();
+
+        let error = hash_dir(&dir).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::Unsupported);
+        assert_eq!(
+            error.get_ref().and_then(|inner| inner.downcast_ref::<SpecialFileError>()),
+            Some(&SpecialFileError { path: socket })
+        );
+
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_non_utf8_link_targets_stay_distinct() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+        use std::os::unix::fs::symlink;
+
+        let a = scratch_dir("link-a");
+        let b = scratch_dir("link-b");
+        symlink(OsStr::from_bytes(b"target-\xff"), a.join("link")).unwrap();
+        symlink(OsStr::from_bytes(b"target-\xfe"), b.join("link")).unwrap();
+
+        let (a_tree, b_tree) = (hash_dir(&a).unwrap(), hash_dir(&b).unwrap());
+        assert_eq!(a_tree.entries[0].hash, MerkleTree::hash_leaf(b"target-\xff"));
+        assert_eq!(a_tree.entries[0].size, 8);
+        assert_ne!(a_tree.root(), b_tree.root());
+
+        fs::remove_dir_all(a).ok();
+        fs::remove_dir_all(b).ok();
+    }
+}
@@ -0,0 +1,327 @@
+use crate::merkle::error::{MerkleError, ProofError};
+use crate::merkle::tree::{MerkleTree, ProofElement};
+
+#[derive(Debug, Clone, Default)]
+pub struct MerkleMountainRange {
+    pub nodes: Vec<String>,
+    pub leaf_count: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct MmrProof {
+    pub position: usize,
+    pub leaf_hash: String,
+    pub mmr_size: usize,
+    pub proof_path: Vec<ProofElement>,
+    pub peaks: Vec<String>,
+}
+
+// Height of the node at a 0-based position in the flat node list.
+fn height_at(position: usize) -> usize {
+    let mut pos = position + 1;
+
+    // Jump left until `pos` is the root of a perfect tree (all ones in binary).
+    while (pos + 1) & pos != 0 {
+        let msb = 1 << (usize::BITS - 1 - pos.leading_zeros());
+        pos = pos - msb + 1;
+    }
+
+    (usize::BITS - pos.leading_zeros()) as usize - 1
+}
+
+fn peak_positions(size: usize) -> Vec<usize> {
+    let mut peaks = Vec::new();
+    let mut offset = 0;
+    let mut remaining = size;
+
+    for height in (0..usize::BITS as usize - 1).rev() {
+        let tree_size = (1 << (height + 1)) - 1;
+
+        if remaining >= tree_size {
+            peaks.push(offset + tree_size - 1);
+            offset += tree_size;
+            remaining -= tree_size;
+        }
+    }
+
+    peaks
+}
+
+// A valid size is a sum of perfect trees of distinct heights, which is
+// exactly when the peaks cover every node.
+fn is_valid_size(size: usize) -> bool {
+    peak_positions(size).last().map_or(size == 0, |&last| last + 1 == size)
+}
+
+// Returns the sibling position and whether the sibling is on the right.
+fn sibling_of(position: usize, height: usize) -> (usize, bool) {
+    if height_at(position + 1) > height {
+        (position + 1 - (2 << height), false)
+    } else {
+        (position + (2 << height) - 1, true)
+    }
+}
+
+fn bag_peaks(peaks: &[String]) -> String {
+    let mut peaks = peaks.iter().rev();
+
+    let Some(last) = peaks.next() else {
+        return String::new();
+    };
+
+    peaks.fold(last.clone(), |root, peak| MerkleTree::hash_pair(peak, &root))
+}
+
+impl MerkleMountainRange {
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn size(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn append(&mut self, leaf: String) -> usize {
+        let position = self.nodes.len();
+        self.nodes.push(MerkleTree::hash_leaf(leaf.as_bytes()));
+        self.leaf_count += 1;
+
+        let mut height = 0;
+
+        while height_at(self.nodes.len()) > height {
+            let left = &self.nodes[self.nodes.len() - (2 << height)];
+            let right = &self.nodes[self.nodes.len() - 1];
+
+            let parent = MerkleTree::hash_pair(left, right);
+            self.nodes.push(parent);
+            height += 1;
+        }
+
+        position
+    }
+
+    pub fn peaks(&self) -> Vec<String> {
+        peak_positions(self.nodes.len())
+            .into_iter()
+            .map(|position| self.nodes[position].clone())
+            .collect()
+    }
+
+    pub fn root(&self) -> String {
+        bag_peaks(&self.peaks())
+    }
+
+    pub fn prove(&self, position: usize) -> Result<MmrProof, MerkleError> {
+        if position >= self.nodes.len() {
+            return Err(MerkleError::PositionOutOfRange {
+                position,
+                size: self.nodes.len(),
+            });
+        }
+        if height_at(position) != 0 {
+            return Err(MerkleError::NotALeaf { position });
+        }
+
+        let peaks = peak_positions(self.nodes.len());
+        let mut proof_path = Vec::new();
+        let mut current = position;
+        let mut height = 0;
+
+        while !peaks.contains(&current) {
+            let (sibling, is_right) = sibling_of(current, height);
+
+            proof_path.push(ProofElement {
+                hash: self.nodes[sibling].clone(),
+                is_right,
+            });
+
+            current = if is_right { sibling + 1 } else { current + 1 };
+            height += 1;
+        }
+
+        Ok(MmrProof {
+            position,
+            leaf_hash: self.nodes[position].clone(),
+            mmr_size: self.nodes.len(),
+            proof_path,
+            peaks: self.peaks(),
+        })
+    }
+}
+
+impl MmrProof {
+
+    pub fn verify(&self, root: &str) -> Result<(), ProofError> {
+        let path_mismatch = ProofError::MmrPathMismatch {
+            position: self.position,
+            size: self.mmr_size,
+        };
+
+        if !is_valid_size(self.mmr_size) {
+            return Err(ProofError::InvalidMmrSize(self.mmr_size));
+        }
+        if self.position >= self.mmr_size {
+            return Err(ProofError::PositionOutOfRange {
+                position: self.position,
+                size: self.mmr_size,
+            });
+        }
+        if height_at(self.position) != 0 {
+            return Err(path_mismatch);
+        }
+
+        let peak_positions = peak_positions(self.mmr_size);
+        if peak_positions.len() != self.peaks.len() {
+            return Err(ProofError::HashCountMismatch {
+                expected: peak_positions.len(),
+                actual: self.peaks.len(),
+            });
+        }
+
+        let mut current = self.position;
+        let mut current_hash = self.leaf_hash.clone();
+
+        for (height, element) in self.proof_path.iter().enumerate() {
+            let (sibling, is_right) = sibling_of(current, height);
+
+            if is_right != element.is_right || sibling >= self.mmr_size {
+                return Err(path_mismatch);
+            }
+
+            if is_right {
+                current_hash = MerkleTree::hash_pair(&current_hash, &element.hash);
+                current = sibling + 1;
+            } else {
+                current_hash = MerkleTree::hash_pair(&element.hash, &current_hash);
+                current += 1;
+            }
+        }
+
+        let Some(peak_index) = peak_positions.iter().position(|&peak| peak == current) else {
+            return Err(path_mismatch);
+        };
+
+        if self.peaks[peak_index] != current_hash {
+            return Err(ProofError::RootMismatch {
+                expected: self.peaks[peak_index].clone(),
+                actual: current_hash,
+            });
+        }
+
+        let bagged = bag_peaks(&self.peaks);
+        if bagged != root {
+            return Err(ProofError::RootMismatch {
+                expected: root.to_string(),
+                actual: bagged,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_mmr(leaves: usize) -> (MerkleMountainRange, Vec<usize>) {
+        let mut mmr = MerkleMountainRange::new();
+        let positions = (0..leaves).map(|i| mmr.append(format!("Leaf {}", i))).collect();
+        (mmr, positions)
+    }
+
+    #[test]
+    fn test_append_positions_and_size() {
+        let (mmr, positions) = sample_mmr(11);
+
+        assert_eq!(positions, vec![0, 1, 3, 4, 7, 8, 10, 11, 15, 16, 18]);
+        assert_eq!(mmr.size(), 19);
+        assert_eq!(mmr.leaf_count, 11);
+        assert_eq!(mmr.peaks().len(), 3, "11 = 8 + 2 + 1 leaves should give three peaks");
+    }
+
+    #[test]
+    fn test_single_peak_matches_merkle_tree() {
+        let (mmr, _) = sample_mmr(8);
+        let tree = MerkleTree::new((0..8).map(|i| format!("Leaf {}", i)).collect());
+
+        assert_eq!(mmr.peaks(), vec![tree.root.clone()]);
+        assert_eq!(mmr.root(), tree.root);
+    }
+
+    #[test]
+    fn test_inclusion_proofs_verify() {
+        for leaves in 1..=20 {
+            let (mmr, positions) = sample_mmr(leaves);
+            let root = mmr.root();
+
+            for &position in &positions {
+                let proof = mmr.prove(position).expect("Should generate proof");
+                assert_eq!(proof.verify(&root), Ok(()), "Leaf at {} of {} leaves should verify", position, leaves);
+            }
+        }
+    }
+
+    #[test]
+    fn test_old_proof_fails_against_new_root() {
+        let (mut mmr, positions) = sample_mmr(6);
+        let proof = mmr.prove(positions[2]).unwrap();
+
+        mmr.append("Leaf 6".to_string());
+        assert!(matches!(proof.verify(&mmr.root()), Err(ProofError::RootMismatch { .. })));
+        assert_eq!(mmr.prove(positions[2]).unwrap().verify(&mmr.root()), Ok(()));
+    }
+
+    #[test]
+    fn test_invalid_mmr_proof_detection() {
+        let (mmr, positions) = sample_mmr(13);
+        let root = mmr.root();
+        let proof = mmr.prove(positions[5]).unwrap();
+
+        let mut tampered = proof.clone();
+        tampered.proof_path[0].hash = "0".repeat(128);
+        assert!(matches!(tampered.verify(&root), Err(ProofError::RootMismatch { .. })));
+
+        let mut tampered = proof.clone();
+        tampered.position = positions[4];
+        assert_eq!(
+            tampered.verify(&root),
+            Err(ProofError::MmrPathMismatch { position: positions[4], size: mmr.size() })
+        );
+
+        let mut tampered = proof.clone();
+        tampered.position = mmr.size();
+        assert_eq!(
+            tampered.verify(&root),
+            Err(ProofError::PositionOutOfRange { position: mmr.size(), size: mmr.size() })
+        );
+
+        let mut tampered = proof;
+        tampered.peaks.swap(0, 1);
+        assert!(matches!(tampered.verify(&root), Err(ProofError::RootMismatch { .. })));
+
+        assert_eq!(mmr.prove(2).unwrap_err(), MerkleError::NotALeaf { position: 2 }, "Internal nodes are not leaves");
+        assert_eq!(
+            mmr.prove(mmr.size()).unwrap_err(),
+            MerkleError::PositionOutOfRange { position: mmr.size(), size: mmr.size() }
+        );
+    }
+
+    #[test]
+    fn test_invalid_mmr_size_rejected() {
+        let valid: Vec<usize> = (0..12).map(|leaves| sample_mmr(leaves).0.size()).collect();
+        assert!(valid.iter().all(|&size| is_valid_size(size)));
+
+        for size in [2, 5, 6, 9, 13, 14, 17] {
+            assert!(!valid.contains(&size));
+            assert!(!is_valid_size(size), "{} should not be an MMR size", size);
+        }
+
+        // Growing the size past the real one would hide peaks from the bagging.
+        let (mmr, positions) = sample_mmr(4);
+        let mut proof = mmr.prove(positions[0]).unwrap();
+        proof.mmr_size = 9;
+        assert_eq!(proof.verify(&mmr.root()), Err(ProofError::InvalidMmrSize(9)));
+    }
+}
@@ -0,0 +1,42 @@
+pub mod bitcoin;
+pub mod builder;
+pub mod chunking;
+pub mod consistency;
+pub mod ct;
+pub mod diff;
+pub mod encoding;
+pub mod error;
+pub mod fs;
+pub mod mmr;
+pub mod mode;
+pub mod multiproof;
+pub mod node_store;
+#[cfg(feature = "parallel")]
+pub mod parallel;
+pub mod range;
+pub mod render;
+pub mod sorted;
+pub mod sparse;
+pub mod store;
+pub mod streaming;
+pub mod sum;
+pub mod tree;
+
+pub use builder::{BuilderError, MerkleTreeBuilder};
+pub use chunking::{Chunk, ChunkedFile, ChunkerConfig, ChunkerError};
+pub use consistency::verify_consistency;
+pub use ct::{ConsistencyProof, InclusionProof, SignedTreeHead, TreeHead};
+pub use encoding::EncodingError;
+pub use error::{IoError, MerkleError, ProofError};
+pub use fs::{diff_dirs, hash_dir, DirTree, Entry, EntryKind, PathProof, SpecialFileError};
+pub use mmr::{MerkleMountainRange, MmrProof};
+pub use mode::HashMode;
+pub use multiproof::MultiProof;
+pub use node_store::{FileStore, MemoryStore, NodeStore};
+pub use range::RangeProof;
+pub use sorted::{ExclusionProof, SortOrder, SortedMerkleTree};
+pub use sparse::{SparseMerkleTree, SparseProof};
+pub use store::{DiskMerkleTree, DiskMerkleTreeBuilder};
+pub use streaming::MerkleRootBuilder;
+pub use sum::{MerkleSumTree, SumNode, SumProof, SumProofElement};
+pub use tree::{MerkleProof, MerkleTree, OddNodePolicy, ProofElement, DEFAULT_ARITY};
@@ -0,0 +1,57 @@
+use crate::hash::sha256::SHA256;
+use crate::merkle::bitcoin;
+use crate::merkle::ct;
+use crate::merkle::tree::MerkleTree;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HashMode {
+    #[default]
+    Sha512,
+    // Double SHA-256 over hashes in Bitcoin's internal byte order. Hashes are
+    // stored reversed, the way Bitcoin RPCs and explorers print txids.
+    Bitcoin,
+    // SHA-256 with the 0x00 leaf and 0x01 node prefixes of RFC 6962. Used with
+    // `OddNodePolicy::Promote` it gives the RFC tree shape.
+    Rfc6962,
+}
+
+impl HashMode {
+
+    pub fn hash_len(&self) -> usize {
+        match self {
+            HashMode::Sha512 => 64,
+            HashMode::Bitcoin | HashMode::Rfc6962 => 32,
+        }
+    }
+
+    // Hashes are lowercase hex of exactly `hash_len` bytes, so each has a
+    // single spelling and concatenated children cannot be split differently.
+    pub fn is_valid_hash(&self, hash: &str) -> bool {
+        hash.len() == self.hash_len() * 2 && hash.bytes().all(|byte| matches!(byte, b'0'..=b'9' | b'a'..=b'f'))
+    }
+
+    pub fn hash_leaf(&self, data: &[u8]) -> String {
+        match self {
+            HashMode::Sha512 => MerkleTree::hash_leaf(data),
+            HashMode::Bitcoin => bitcoin::to_display(SHA256::double_hash(data)),
+            HashMode::Rfc6962 => ct::leaf_hash(data),
+        }
+    }
+
+    // Children must be well-formed hashes for the mode.
+    pub(crate) fn hash_children(&self, children: &[&str]) -> String {
+        match self {
+            HashMode::Sha512 => MerkleTree::hash_children(children),
+            HashMode::Bitcoin => {
+                let bytes: Vec<u8> = children.iter().flat_map(|child| bitcoin::to_internal(child)).collect();
+                bitcoin::to_display(SHA256::double_hash(&bytes))
+            }
+            HashMode::Rfc6962 => ct::hash_children(children),
+        }
+    }
+
+    pub(crate) fn zero_hash(&self) -> String {
+        "0".repeat(self.hash_len() * 2)
+    }
+}
@@ -0,0 +1,285 @@
+use crate::merkle::error::{MerkleError, ProofError};
+use crate::merkle::node_store::NodeStore;
+use crate::merkle::tree::MerkleTree;
+
+#[derive(Debug, Clone)]
+pub struct MultiProof {
+    pub leaf_count: usize,
+    pub leaf_indices: Vec<usize>,
+    pub leaf_hashes: Vec<String>,
+    pub hashes: Vec<String>,
+}
+
+impl<S: NodeStore> MerkleTree<S> {
+
+    pub fn get_multiproof(&self, leaf_indices: &[usize]) -> Result<MultiProof, MerkleError> {
+        let mut indices = leaf_indices.to_vec();
+        indices.sort_unstable();
+        indices.dedup();
+
+        if !self.has_default_shape() {
+            return Err(MerkleError::UnsupportedShape);
+        }
+        let Some(&last) = indices.last() else {
+            return Err(MerkleError::EmptySelection);
+        };
+        if last >= self.leaf_count() {
+            return Err(MerkleError::IndexOutOfRange {
+                index: last,
+                leaf_count: self.leaf_count(),
+            });
+        }
+
+        let leaf_hashes = indices
+            .iter()
+            .map(|&i| self.node(0, i))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut hashes = Vec::new();
+        let mut known = indices.clone();
+
+        for level_idx in 0..self.height() - 1 {
+            let mut parents = Vec::new();
+            let mut pos = 0;
+
+            while pos < known.len() {
+                let index = known[pos];
+                let sibling_index = index ^ 1;
+
+                if pos + 1 < known.len() && known[pos + 1] == sibling_index {
+                    pos += 2;
+                } else {
+                    // A missing right sibling on an odd level is the node
+                    // itself, which the verifier already has.
+                    if sibling_index < self.store.len(level_idx) {
+                        hashes.push(self.node(level_idx, sibling_index)?);
+                    }
+                    pos += 1;
+                }
+
+                parents.push(index / 2);
+            }
+
+            known = parents;
+        }
+
+        Ok(MultiProof {
+            leaf_count: self.leaf_count(),
+            leaf_indices: indices,
+            leaf_hashes,
+            hashes,
+        })
+    }
+}
+
+impl MultiProof {
+
+    pub fn verify(&self, root: &str) -> Result<(), ProofError> {
+        if self.leaf_indices.len() != self.leaf_hashes.len() {
+            return Err(ProofError::LeafCountMismatch {
+                expected: self.leaf_indices.len(),
+                actual: self.leaf_hashes.len(),
+            });
+        }
+
+        let strictly_increasing = self.leaf_indices.windows(2).all(|w| w[0] < w[1]);
+        let Some(&last) = self.leaf_indices.last().filter(|_| strictly_increasing) else {
+            return Err(ProofError::InvalidLeafIndices);
+        };
+        if last >= self.leaf_count {
+            return Err(ProofError::IndexOutOfRange {
+                index: last,
+                leaf_count: self.leaf_count,
+            });
+        }
+
+        let mut known: Vec<(usize, String)> = self
+            .leaf_indices
+            .iter()
+            .copied()
+            .zip(self.leaf_hashes.iter().cloned())
+            .collect();
+        // Missing siblings are counted rather than returned early so the error
+        // can report how many hashes the proof should have held.
+        let mut needed = 0;
+        let mut level_len = self.leaf_count;
+
+        while level_len > 1 {
+            let mut parents = Vec::new();
+            let mut pos = 0;
+
+            while pos < known.len() {
+                let (index, ref hash) = known[pos];
+                let sibling_index = index ^ 1;
+
+                let sibling_hash = if pos + 1 < known.len() && known[pos + 1].0 == sibling_index {
+                    pos += 1;
+                    known[pos].1.clone()
+                } else if sibling_index >= level_len {
+                    hash.clone()
+                } else {
+                    needed += 1;
+                    self.hashes.get(needed - 1).unwrap_or(hash).clone()
+                };
+                pos += 1;
+
+                let parent_hash = if index % 2 == 0 {
+                    MerkleTree::hash_pair(hash, &sibling_hash)
+                } else {
+                    MerkleTree::hash_pair(&sibling_hash, hash)
+                };
+
+                parents.push((index / 2, parent_hash));
+            }
+
+            known = parents;
+            level_len = level_len.div_ceil(2);
+        }
+
+        if needed != self.hashes.len() {
+            return Err(ProofError::HashCountMismatch {
+                expected: needed,
+                actual: self.hashes.len(),
+            });
+        }
+
+        let (_, computed) = known.remove(0);
+        if computed != root {
+            return Err(ProofError::RootMismatch {
+                expected: root.to_string(),
+                actual: computed,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_tree(leaves: usize) -> MerkleTree {
+        MerkleTree::new((0..leaves).map(|i| format!("Tx {}", i)).collect())
+    }
+
+    fn random_subset(seed: &mut u64, leaf_count: usize, size: usize) -> Vec<usize> {
+        let mut subset = Vec::new();
+        while subset.len() < size {
+            *seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            let index = (*seed >> 33) as usize % leaf_count;
+            if !subset.contains(&index) {
+                subset.push(index);
+            }
+        }
+        subset
+    }
+
+    #[test]
+    fn test_multiproof_verification() {
+        let tree = sample_tree(8);
+        let proof = tree.get_multiproof(&[1, 4, 6]).expect("Should generate multiproof");
+
+        assert_eq!(proof.leaf_indices, vec![1, 4, 6]);
+        assert_eq!(proof.verify(&tree.root), Ok(()), "Multiproof should be valid");
+    }
+
+    #[test]
+    fn test_multiproof_odd_leaf_counts() {
+        for leaves in 1..=33 {
+            let tree = sample_tree(leaves);
+            let all: Vec<usize> = (0..leaves).collect();
+
+            let proof = tree.get_multiproof(&all).expect("Should generate multiproof");
+            assert!(proof.hashes.is_empty(), "Proving every leaf needs no siblings");
+            assert_eq!(proof.verify(&tree.root), Ok(()), "Failed for {} leaves", leaves);
+
+            let last = tree.get_multiproof(&[leaves - 1]).expect("Should generate multiproof");
+            assert_eq!(last.verify(&tree.root), Ok(()), "Last leaf failed for {} leaves", leaves);
+        }
+    }
+
+    #[test]
+    fn test_multiproof_random_subsets_smaller_than_naive() {
+        let mut seed = 42;
+
+        for &leaves in &[16, 100, 257, 1000] {
+            let tree = sample_tree(leaves);
+
+            for &size in &[2, 10, leaves / 4] {
+                let subset = random_subset(&mut seed, leaves, size);
+                let proof = tree.get_multiproof(&subset).expect("Should generate multiproof");
+
+                let naive: usize = subset
+                    .iter()
+                    .map(|&i| tree.get_proof(i).expect("Should generate proof").proof_path.len())
+                    .sum();
+
+                assert_eq!(proof.verify(&tree.root), Ok(()));
+                assert!(
+                    proof.hashes.len() < naive,
+                    "Multiproof with {} hashes should beat {} naive hashes",
+                    proof.hashes.len(),
+                    naive
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_invalid_multiproof_detection() {
+        let tree = sample_tree(10);
+        let proof = tree.get_multiproof(&[2, 7]).expect("Should generate multiproof");
+
+        let mut tampered = proof.clone();
+        tampered.leaf_hashes[0] = "0".repeat(128);
+        assert!(
+            matches!(tampered.verify(&tree.root), Err(ProofError::RootMismatch { .. })),
+            "Tampered leaf should fail verification"
+        );
+
+        let expected = proof.hashes.len();
+
+        let mut tampered = proof.clone();
+        tampered.hashes.pop();
+        assert_eq!(
+            tampered.verify(&tree.root),
+            Err(ProofError::HashCountMismatch { expected, actual: expected - 1 }),
+            "Truncated proof should fail verification"
+        );
+
+        let mut tampered = proof.clone();
+        tampered.hashes.push("0".repeat(128));
+        assert_eq!(
+            tampered.verify(&tree.root),
+            Err(ProofError::HashCountMismatch { expected, actual: expected + 1 }),
+            "Extra hashes should fail verification"
+        );
+
+        let mut tampered = proof.clone();
+        tampered.leaf_indices = vec![7, 2];
+        assert_eq!(
+            tampered.verify(&tree.root),
+            Err(ProofError::InvalidLeafIndices),
+            "Unsorted indices should fail verification"
+        );
+
+        let mut tampered = proof;
+        tampered.leaf_hashes.pop();
+        assert_eq!(
+            tampered.verify(&tree.root),
+            Err(ProofError::LeafCountMismatch { expected: 2, actual: 1 })
+        );
+    }
+
+    #[test]
+    fn test_multiproof_out_of_range() {
+        let tree = sample_tree(4);
+
+        assert_eq!(tree.get_multiproof(&[]).unwrap_err(), MerkleError::EmptySelection);
+        assert_eq!(
+            tree.get_multiproof(&[1, 4]).unwrap_err(),
+            MerkleError::IndexOutOfRange { index: 4, leaf_count: 4 }
+        );
+    }
+}
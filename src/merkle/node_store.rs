@@ -0,0 +1,360 @@
+use crate::merkle::encoding::HASH_SIZE;
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+// Backing storage for the nodes of a `MerkleTree`. Level 0 holds the leaf
+// hashes; `put` may overwrite an existing node or append one past the end of
+// a level (or start the next level), but never leave gaps. `get` returns
+// `Ok(None)` only for a node that was never stored. `clear` runs before a
+// tree is built into the store; a store that must keep its history may
+// refuse it instead of discarding nodes.
+pub trait NodeStore {
+    fn get(&self, level: usize, index: usize) -> io::Result<Option<String>>;
+    fn put(&mut self, level: usize, index: usize, hash: String) -> io::Result<()>;
+    fn clear(&mut self) -> io::Result<()>;
+    fn len(&self, level: usize) -> usize;
+    fn height(&self) -> usize;
+}
+
+fn check_position(height: usize, level: usize, len: usize, index: usize) -> io::Result<()> {
+    if level > height || index > len {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("node ({}, {}) would leave a gap in the store", level, index),
+        ));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MemoryStore {
+    pub levels: Vec<Vec<String>>,
+}
+
+impl MemoryStore {
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl NodeStore for MemoryStore {
+
+    fn get(&self, level: usize, index: usize) -> io::Result<Option<String>> {
+        Ok(self.levels.get(level).and_then(|nodes| nodes.get(index)).cloned())
+    }
+
+    fn put(&mut self, level: usize, index: usize, hash: String) -> io::Result<()> {
+        check_position(self.height(), level, self.len(level), index)?;
+
+        if level == self.levels.len() {
+            self.levels.push(Vec::new());
+        }
+
+        let nodes = &mut self.levels[level];
+        if index == nodes.len() {
+            nodes.push(hash);
+        } else {
+            nodes[index] = hash;
+        }
+        Ok(())
+    }
+
+    fn clear(&mut self) -> io::Result<()> {
+        self.levels.clear();
+        Ok(())
+    }
+
+    fn len(&self, level: usize) -> usize {
+        self.levels.get(level).map(|nodes| nodes.len()).unwrap_or(0)
+    }
+
+    fn height(&self) -> usize {
+        self.levels.len()
+    }
+}
+
+// Record layout: level (u32 BE) | index (u64 BE) | raw 64-byte hash
+const RECORD_SIZE: usize = 4 + 8 + HASH_SIZE;
+
+// Append-only node log. Every `put` appends a record and later records win,
+// so an update never rewrites bytes already on disk. The in-memory index
+// only keeps the file offset of the latest record for each node. A log is
+// never cleared: building a new tree needs a fresh log from `create`.
+#[derive(Debug)]
+pub struct FileStore {
+    file: File,
+    offsets: Vec<Vec<u64>>,
+    end: u64,
+}
+
+impl FileStore {
+
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)?;
+
+        Ok(FileStore {
+            file,
+            offsets: Vec::new(),
+            end: 0,
+        })
+    }
+
+    // Replays the log one record at a time, so only the offsets index is
+    // held in memory.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref();
+        let file = OpenOptions::new().read(true).write(true).open(path)?;
+        let end = file.metadata()?.len();
+
+        if end % RECORD_SIZE as u64 != 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "truncated node record"));
+        }
+
+        let mut store = FileStore {
+            file,
+            offsets: Vec::new(),
+            end,
+        };
+        let mut reader = BufReader::new(File::open(path)?);
+        let mut record = [0u8; RECORD_SIZE];
+
+        for i in 0..end / RECORD_SIZE as u64 {
+            reader.read_exact(&mut record)?;
+
+            let mut level = [0u8; 4];
+            level.copy_from_slice(&record[..4]);
+            let mut index = [0u8; 8];
+            index.copy_from_slice(&record[4..12]);
+
+            let level = u32::from_be_bytes(level) as usize;
+            let index = u64::from_be_bytes(index) as usize;
+
+            check_position(store.height(), level, store.len(level), index)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            store.record(level, index, i * RECORD_SIZE as u64);
+        }
+
+        Ok(store)
+    }
+
+    pub fn sync(&self) -> io::Result<()> {
+        self.file.sync_all()
+    }
+
+    fn record(&mut self, level: usize, index: usize, offset: u64) {
+        if level == self.offsets.len() {
+            self.offsets.push(Vec::new());
+        }
+
+        let offsets = &mut self.offsets[level];
+        if index == offsets.len() {
+            offsets.push(offset);
+        } else {
+            offsets[index] = offset;
+        }
+    }
+}
+
+// Positional reads leave the shared cursor alone, so `get` works through
+// `&self` without racing `put`.
+#[cfg(unix)]
+fn read_at(file: &File, buf: &mut [u8], offset: u64) -> io::Result<()> {
+    use std::os::unix::fs::FileExt;
+    file.read_exact_at(buf, offset)
+}
+
+#[cfg(windows)]
+fn read_at(file: &File, buf: &mut [u8], offset: u64) -> io::Result<()> {
+    use std::os::windows::fs::FileExt;
+
+    let mut read = 0;
+    while read < buf.len() {
+        match file.seek_read(&mut buf[read..], offset + read as u64)? {
+            0 => return Err(io::Error::from(io::ErrorKind::UnexpectedEof)),
+            n => read += n,
+        }
+    }
+    Ok(())
+}
+
+impl NodeStore for FileStore {
+
+    fn get(&self, level: usize, index: usize) -> io::Result<Option<String>> {
+        let Some(&offset) = self.offsets.get(level).and_then(|offsets| offsets.get(index)) else {
+            return Ok(None);
+        };
+
+        let mut hash = [0u8; HASH_SIZE];
+        read_at(&self.file, &mut hash, offset + 12)?;
+
+        Ok(Some(hex::encode(hash)))
+    }
+
+    fn put(&mut self, level: usize, index: usize, hash: String) -> io::Result<()> {
+        check_position(self.height(), level, self.len(level), index)?;
+
+        let hash = hex::decode(&hash)
+            .ok()
+            .filter(|bytes| bytes.len() == HASH_SIZE)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "node hash is not a 64-byte hex digest"))?;
+
+        let mut record = Vec::with_capacity(RECORD_SIZE);
+        record.extend_from_slice(&(level as u32).to_be_bytes());
+        record.extend_from_slice(&(index as u64).to_be_bytes());
+        record.extend_from_slice(&hash);
+
+        self.file.seek(SeekFrom::Start(self.end))?;
+        self.file.write_all(&record)?;
+
+        let offset = self.end;
+        self.end += RECORD_SIZE as u64;
+        self.record(level, index, offset);
+        Ok(())
+    }
+
+    fn clear(&mut self) -> io::Result<()> {
+        if self.end != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                "node log already holds a tree; rebuild into a new log",
+            ));
+        }
+        Ok(())
+    }
+
+    fn len(&self, level: usize) -> usize {
+        self.offsets.get(level).map(|offsets| offsets.len()).unwrap_or(0)
+    }
+
+    fn height(&self) -> usize {
+        self.offsets.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merkle::error::MerkleError;
+    use crate::merkle::tree::MerkleTree;
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("merkletree-nodes-{}-{}.log", name, std::process::id()))
+    }
+
+    fn leaves(count: usize) -> Vec<String> {
+        (0..count).map(|i| format!("Row {}", i)).collect()
+    }
+
+    #[test]
+    fn test_memory_store_rejects_gaps() {
+        let mut store = MemoryStore::new();
+
+        assert!(store.put(0, 0, "a".to_string()).is_ok());
+        assert!(store.put(0, 2, "c".to_string()).is_err());
+        assert!(store.put(2, 0, "c".to_string()).is_err());
+        assert!(store.put(0, 0, "b".to_string()).is_ok());
+        assert_eq!(store.get(0, 0).unwrap(), Some("b".to_string()));
+        assert_eq!(store.get(0, 1).unwrap(), None);
+    }
+
+    #[test]
+    fn test_file_store_tree_matches_memory_tree() {
+        let path = temp_path("build");
+        let store = FileStore::create(&path).unwrap();
+
+        let tree = MerkleTree::with_store(store, leaves(9)).expect("Should build tree on disk");
+        let memory = MerkleTree::new(leaves(9));
+
+        assert_eq!(tree.root, memory.root);
+        for i in 0..9 {
+            assert_eq!(tree.get_proof(i), memory.get_proof(i));
+        }
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_file_store_replays_updates() {
+        let path = temp_path("replay");
+
+        let mut tree = MerkleTree::with_store(FileStore::create(&path).unwrap(), leaves(6)).unwrap();
+        tree.update(4, "Row 4 (corrected)".to_string()).unwrap();
+        let root = tree.root.clone();
+
+        let reopened = MerkleTree::from_store(FileStore::open(&path).expect("Should replay node log")).unwrap();
+        assert_eq!(reopened.root, root);
+
+        let mut expected = leaves(6);
+        expected[4] = "Row 4 (corrected)".to_string();
+        assert_eq!(root, MerkleTree::new(expected).root);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_rebuild_clears_previous_nodes() {
+        let memory = MerkleTree::new(leaves(9));
+        let rebuilt = MerkleTree::with_store(memory.store, leaves(3)).unwrap();
+        assert_eq!(rebuilt, MerkleTree::new(leaves(3)));
+    }
+
+    #[test]
+    fn test_file_store_keeps_its_history() {
+        let path = temp_path("rebuild");
+        let tree = MerkleTree::with_store(FileStore::create(&path).unwrap(), leaves(9)).unwrap();
+        let root = tree.root.clone();
+
+        let error = MerkleTree::with_store(tree.store, leaves(3)).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::AlreadyExists);
+
+        let reopened = MerkleTree::from_store(FileStore::open(&path).unwrap()).unwrap();
+        assert_eq!(reopened.root, root);
+        assert_eq!(reopened.leaf_count(), 9);
+
+        let error = MerkleTree::with_store(FileStore::open(&path).unwrap(), leaves(3)).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::AlreadyExists);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_file_store_reports_read_errors() {
+        let path = temp_path("shrunk");
+        let tree = MerkleTree::with_store(FileStore::create(&path).unwrap(), leaves(4)).unwrap();
+
+        // Cut the log behind the store's back so the indexed records vanish.
+        OpenOptions::new().write(true).open(&path).unwrap().set_len(0).unwrap();
+
+        assert_eq!(tree.store.get(0, 0).unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+
+        let error = tree.get_proof(0).unwrap_err();
+        assert!(matches!(&error, MerkleError::Io(err) if err.kind() == io::ErrorKind::UnexpectedEof));
+        let source = std::error::Error::source(&error).expect("Should keep the io error");
+        assert!(source.downcast_ref::<io::Error>().is_some());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_file_store_rejects_truncated_log() {
+        let path = temp_path("truncated");
+
+        MerkleTree::with_store(FileStore::create(&path).unwrap(), leaves(3)).unwrap();
+
+        let mut bytes = std::fs::read(&path).unwrap();
+        bytes.truncate(bytes.len() - 1);
+        std::fs::write(&path, &bytes).unwrap();
+
+        assert_eq!(FileStore::open(&path).unwrap_err().kind(), io::ErrorKind::InvalidData);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
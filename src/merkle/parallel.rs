@@ -0,0 +1,106 @@
+use crate::merkle::mode::HashMode;
+use crate::merkle::tree::{MerkleTree, OddNodePolicy};
+use rayon::prelude::*;
+
+// Below this many nodes a level is hashed on the calling thread; splitting
+// small levels across the pool costs more than it saves.
+pub const PARALLEL_THRESHOLD: usize = 4096;
+
+// Chunk sizes are kept a multiple of the arity so every group stays within
+// one chunk and the parents come out in the same order as the serial pass.
+const CHUNK_SIZE: usize = 1024;
+
+pub(crate) fn hash_leaves(data: &[String], mode: HashMode) -> Vec<String> {
+    data.par_chunks(CHUNK_SIZE)
+        .flat_map_iter(|chunk| chunk.iter().map(move |item| mode.hash_leaf(item.as_bytes())))
+        .collect()
+}
+
+pub(crate) fn hash_level(
+    level: &[String],
+    arity: usize,
+    policy: OddNodePolicy,
+    padding: Option<&str>,
+    mode: HashMode,
+) -> Vec<String> {
+    level
+        .par_chunks(CHUNK_SIZE.div_ceil(arity) * arity)
+        .flat_map_iter(|chunk| {
+            chunk
+                .chunks(arity)
+                .map(move |group| MerkleTree::hash_group(group, arity, policy, padding, mode))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn serial_root(data: &[String]) -> String {
+        let mut level: Vec<String> = data
+            .iter()
+            .map(|item| MerkleTree::hash_leaf(item.as_bytes()))
+            .collect();
+
+        while level.len() > 1 {
+            level = level
+                .chunks(2)
+                .map(|pair| MerkleTree::hash_pair(&pair[0], pair.get(1).unwrap_or(&pair[0])))
+                .collect();
+        }
+
+        level.pop().unwrap_or_default()
+    }
+
+    fn records(count: usize) -> Vec<String> {
+        (0..count).map(|i| format!("Record {}", i)).collect()
+    }
+
+    #[test]
+    fn test_parallel_level_matches_serial() {
+        for count in [1, 2, 3, CHUNK_SIZE - 1, CHUNK_SIZE + 1, 3 * CHUNK_SIZE + 5] {
+            let leaves = hash_leaves(&records(count), HashMode::Sha512);
+
+            let serial: Vec<String> = leaves
+                .chunks(2)
+                .map(|pair| MerkleTree::hash_pair(&pair[0], pair.get(1).unwrap_or(&pair[0])))
+                .collect();
+
+            assert_eq!(
+                hash_level(&leaves, 2, OddNodePolicy::Duplicate, None, HashMode::Sha512),
+                serial,
+                "Level mismatch for {} nodes",
+                count
+            );
+        }
+    }
+
+    #[test]
+    fn test_parallel_level_keeps_wide_groups_together() {
+        let leaves = hash_leaves(&records(3 * CHUNK_SIZE + 5), HashMode::Sha512);
+
+        for arity in [3, 4, 16] {
+            let serial: Vec<String> = leaves
+                .chunks(arity)
+                .map(|group| MerkleTree::hash_group(group, arity, OddNodePolicy::Duplicate, None, HashMode::Sha512))
+                .collect();
+
+            assert_eq!(
+                hash_level(&leaves, arity, OddNodePolicy::Duplicate, None, HashMode::Sha512),
+                serial,
+                "Level mismatch for arity {}",
+                arity
+            );
+        }
+    }
+
+    #[test]
+    fn test_parallel_root_matches_serial() {
+        for count in [PARALLEL_THRESHOLD - 1, PARALLEL_THRESHOLD, PARALLEL_THRESHOLD * 2 + 3] {
+            let data = records(count);
+
+            assert_eq!(MerkleTree::new(data.clone()).root, serial_root(&data));
+        }
+    }
+}
@@ -0,0 +1,247 @@
+use crate::merkle::error::{MerkleError, ProofError};
+use crate::merkle::node_store::NodeStore;
+use crate::merkle::tree::MerkleTree;
+use std::ops::Range;
+
+// Only the siblings along the two edges of the range are needed; every node
+// strictly inside the range is recomputed from the leaves themselves.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RangeProof {
+    pub leaf_count: usize,
+    pub start: usize,
+    pub end: usize,
+    pub left: Vec<String>,
+    pub right: Vec<String>,
+}
+
+impl<S: NodeStore> MerkleTree<S> {
+
+    pub fn range_proof(&self, range: Range<usize>) -> Result<RangeProof, MerkleError> {
+        if !self.has_default_shape() {
+            return Err(MerkleError::UnsupportedShape);
+        }
+        if range.start >= range.end || range.end > self.leaf_count() {
+            return Err(MerkleError::InvalidRange {
+                start: range.start,
+                end: range.end,
+                leaf_count: self.leaf_count(),
+            });
+        }
+
+        let mut left = Vec::new();
+        let mut right = Vec::new();
+        let mut lo = range.start;
+        let mut hi = range.end;
+
+        for level_idx in 0..self.height() - 1 {
+            if lo % 2 == 1 {
+                left.push(self.node(level_idx, lo - 1)?);
+            }
+
+            // A range ending on the last node of an odd level is padded with
+            // that node itself, which the verifier already has.
+            if hi % 2 == 1 && hi < self.store.len(level_idx) {
+                right.push(self.node(level_idx, hi)?);
+            }
+
+            lo /= 2;
+            hi = hi.div_ceil(2);
+        }
+
+        Ok(RangeProof {
+            leaf_count: self.leaf_count(),
+            start: range.start,
+            end: range.end,
+            left,
+            right,
+        })
+    }
+}
+
+impl RangeProof {
+
+    pub fn verify<T: AsRef<[u8]>>(&self, root: &str, leaves: &[T]) -> Result<(), ProofError> {
+        if self.start >= self.end || self.end > self.leaf_count {
+            return Err(ProofError::InvalidRange {
+                start: self.start,
+                end: self.end,
+                leaf_count: self.leaf_count,
+            });
+        }
+        if leaves.len() != self.end - self.start {
+            return Err(ProofError::LeafCountMismatch {
+                expected: self.end - self.start,
+                actual: leaves.len(),
+            });
+        }
+
+        let mut known: Vec<String> = leaves
+            .iter()
+            .map(|leaf| MerkleTree::hash_leaf(leaf.as_ref()))
+            .collect();
+        // Missing edge hashes are counted rather than returned early so the
+        // error can report how many the proof should have held.
+        let mut left_needed = 0;
+        let mut right_needed = 0;
+        let mut lo = self.start;
+        let mut hi = self.end;
+        let mut level_len = self.leaf_count;
+
+        while level_len > 1 {
+            if lo % 2 == 1 {
+                left_needed += 1;
+                let hash = self.left.get(left_needed - 1).unwrap_or(&known[0]).clone();
+                known.insert(0, hash);
+                lo -= 1;
+            }
+
+            if hi % 2 == 1 {
+                let last = known[known.len() - 1].clone();
+
+                if hi < level_len {
+                    right_needed += 1;
+                    known.push(self.right.get(right_needed - 1).cloned().unwrap_or(last));
+                } else {
+                    known.push(last);
+                }
+                hi += 1;
+            }
+
+            known = known
+                .chunks(2)
+                .map(|pair| MerkleTree::hash_pair(&pair[0], &pair[1]))
+                .collect();
+
+            lo /= 2;
+            hi /= 2;
+            level_len = level_len.div_ceil(2);
+        }
+
+        for (needed, hashes) in [(left_needed, &self.left), (right_needed, &self.right)] {
+            if needed != hashes.len() {
+                return Err(ProofError::HashCountMismatch {
+                    expected: needed,
+                    actual: hashes.len(),
+                });
+            }
+        }
+
+        let computed = known.remove(0);
+        if computed != root {
+            return Err(ProofError::RootMismatch {
+                expected: root.to_string(),
+                actual: computed,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merkle::builder::MerkleTreeBuilder;
+
+    fn entries(count: usize) -> Vec<String> {
+        (0..count).map(|i| format!("Entry {}", i)).collect()
+    }
+
+    #[test]
+    fn test_every_range_verifies() {
+        for count in 1..=17 {
+            let data = entries(count);
+            let tree = MerkleTree::new(data.clone());
+
+            for start in 0..count {
+                for end in start + 1..=count {
+                    let proof = tree.range_proof(start..end).expect("Should generate range proof");
+
+                    assert_eq!(
+                        proof.verify(&tree.root, &data[start..end]),
+                        Ok(()),
+                        "Range {}..{} of {} leaves failed",
+                        start,
+                        end,
+                        count
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_range_proof_smaller_than_individual_proofs() {
+        let tree = MerkleTree::new(entries(1000));
+
+        for range in [0..2, 10..50, 123..457, 999..1000, 0..1000] {
+            let proof = tree.range_proof(range.clone()).unwrap();
+            let naive: usize = range
+                .clone()
+                .map(|i| tree.get_proof(i).unwrap().proof_path.len())
+                .sum();
+
+            assert!(proof.left.len() + proof.right.len() <= 2 * (tree.height() - 1));
+            assert!(proof.left.len() + proof.right.len() <= naive);
+        }
+
+        let whole = tree.range_proof(0..1000).unwrap();
+        assert!(whole.left.is_empty() && whole.right.is_empty());
+    }
+
+    #[test]
+    fn test_incomplete_or_shifted_range_rejected() {
+        let data = entries(20);
+        let tree = MerkleTree::new(data.clone());
+        let proof = tree.range_proof(5..12).unwrap();
+
+        assert_eq!(proof.verify(&tree.root, &data[5..12]), Ok(()));
+
+        // A missing entry, a reordered page or a page claimed at another
+        // position must all fail.
+        let mut missing = data[5..12].to_vec();
+        missing.remove(3);
+        assert_eq!(
+            proof.verify(&tree.root, &missing),
+            Err(ProofError::LeafCountMismatch { expected: 7, actual: 6 })
+        );
+
+        let mut reordered = data[5..12].to_vec();
+        reordered.swap(1, 2);
+        assert!(matches!(proof.verify(&tree.root, &reordered), Err(ProofError::RootMismatch { .. })));
+
+        let mut shifted = proof.clone();
+        shifted.start += 1;
+        shifted.end += 1;
+        assert!(shifted.verify(&tree.root, &data[5..12]).is_err());
+
+        let mut truncated = proof;
+        truncated.right.pop();
+        assert_eq!(
+            truncated.verify(&tree.root, &data[5..12]),
+            Err(ProofError::HashCountMismatch {
+                expected: truncated.right.len() + 1,
+                actual: truncated.right.len(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_invalid_ranges() {
+        let tree = MerkleTree::new(entries(8));
+
+        assert_eq!(
+            tree.range_proof(3..3),
+            Err(MerkleError::InvalidRange { start: 3, end: 3, leaf_count: 8 })
+        );
+        assert_eq!(
+            tree.range_proof(6..9),
+            Err(MerkleError::InvalidRange { start: 6, end: 9, leaf_count: 8 })
+        );
+        assert!(MerkleTree::new(Vec::new()).range_proof(0..1).is_err());
+        assert_eq!(
+            MerkleTreeBuilder::new().arity(4).unwrap().build(entries(8)).range_proof(0..2),
+            Err(MerkleError::UnsupportedShape)
+        );
+    }
+}
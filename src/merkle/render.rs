@@ -0,0 +1,304 @@
+use crate::merkle::node_store::NodeStore;
+use crate::merkle::tree::{short_hash, MerkleProof, MerkleTree, OddNodePolicy};
+use std::collections::HashSet;
+use std::fmt;
+use std::io;
+
+const LABEL_LEN: usize = 12;
+const PATH_COLOR: &str = "#f4a261";
+const SIBLING_COLOR: &str = "#8ecae6";
+
+type NodeSet = HashSet<(usize, usize)>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Highlight {
+    None,
+    Path,
+    Sibling,
+}
+
+// Lets the `fmt::Write` renderers stream into an `io::Write`, keeping the
+// underlying io error that `fmt::Error` cannot carry.
+struct IoAdapter<'a, W: io::Write> {
+    inner: &'a mut W,
+    error: Option<io::Error>,
+}
+
+impl<W: io::Write> IoAdapter<'_, W> {
+    fn finish(self, result: fmt::Result) -> io::Result<()> {
+        match (result, self.error) {
+            (Ok(()), _) => Ok(()),
+            (Err(_), Some(error)) => Err(error),
+            (Err(_), None) => Err(io::Error::other("formatting failed")),
+        }
+    }
+}
+
+impl<W: io::Write> fmt::Write for IoAdapter<'_, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.inner.write_all(s.as_bytes()).map_err(|error| {
+            self.error = Some(error);
+            fmt::Error
+        })
+    }
+}
+
+impl<S: NodeStore> MerkleTree<S> {
+
+    pub fn to_dot(&self, proof: Option<&MerkleProof>) -> String {
+        let mut out = String::new();
+        self.write_dot(&mut out, proof).expect("Writing to a String should not fail");
+        out
+    }
+
+    pub fn to_mermaid(&self, proof: Option<&MerkleProof>) -> String {
+        let mut out = String::new();
+        self.write_mermaid(&mut out, proof).expect("Writing to a String should not fail");
+        out
+    }
+
+    pub fn write_dot_to<W: io::Write>(&self, out: &mut W, proof: Option<&MerkleProof>) -> io::Result<()> {
+        let mut adapter = IoAdapter { inner: out, error: None };
+        let result = self.write_dot(&mut adapter, proof);
+        adapter.finish(result)
+    }
+
+    pub fn write_mermaid_to<W: io::Write>(&self, out: &mut W, proof: Option<&MerkleProof>) -> io::Result<()> {
+        let mut adapter = IoAdapter { inner: out, error: None };
+        let result = self.write_mermaid(&mut adapter, proof);
+        adapter.finish(result)
+    }
+
+    pub fn write_dot<W: fmt::Write>(&self, out: &mut W, proof: Option<&MerkleProof>) -> fmt::Result {
+        let (path, siblings) = self.highlighted(proof);
+
+        writeln!(out, "digraph merkle {{")?;
+        writeln!(out, "  node [shape=box, fontname=\"monospace\"];")?;
+
+        for level in (0..self.height()).rev() {
+            for index in 0..self.store.len(level) {
+                let style = match Self::highlight(&path, &siblings, level, index) {
+                    Highlight::Path => format!(", style=filled, fillcolor=\"{}\"", PATH_COLOR),
+                    Highlight::Sibling => format!(", style=filled, fillcolor=\"{}\"", SIBLING_COLOR),
+                    Highlight::None => String::new(),
+                };
+
+                writeln!(
+                    out,
+                    "  n{}_{} [label=\"{}\"{}];",
+                    level,
+                    index,
+                    self.label(level, index),
+                    style
+                )?;
+            }
+        }
+
+        self.write_edges(out, |out, parent, child, duplicate| {
+            let style = if duplicate { " [style=dashed]" } else { "" };
+            writeln!(out, "  n{}_{} -> n{}_{}{};", parent.0, parent.1, child.0, child.1, style)
+        })?;
+
+        writeln!(out, "}}")
+    }
+
+    pub fn write_mermaid<W: fmt::Write>(&self, out: &mut W, proof: Option<&MerkleProof>) -> fmt::Result {
+        let (path, siblings) = self.highlighted(proof);
+
+        writeln!(out, "graph TD")?;
+
+        for level in (0..self.height()).rev() {
+            for index in 0..self.store.len(level) {
+                writeln!(out, "  n{}_{}[\"{}\"]", level, index, self.label(level, index))?;
+            }
+        }
+
+        self.write_edges(out, |out, parent, child, duplicate| {
+            let arrow = if duplicate { "-.->" } else { "-->" };
+            writeln!(out, "  n{}_{} {} n{}_{}", parent.0, parent.1, arrow, child.0, child.1)
+        })?;
+
+        for (class, color, nodes) in [("path", PATH_COLOR, &path), ("sibling", SIBLING_COLOR, &siblings)] {
+            if nodes.is_empty() {
+                continue;
+            }
+
+            let mut nodes: Vec<_> = nodes.iter().collect();
+            nodes.sort_unstable();
+            let ids: Vec<String> = nodes.iter().map(|(level, index)| format!("n{}_{}", level, index)).collect();
+
+            writeln!(out, "  classDef {} fill:{};", class, color)?;
+            writeln!(out, "  class {} {};", ids.join(","), class)?;
+        }
+
+        Ok(())
+    }
+
+    fn label(&self, level: usize, index: usize) -> String {
+        let hash = self.node(level, index).unwrap_or_default();
+        format!("L{} [{}] {}", level, index, short_hash(&hash, LABEL_LEN))
+    }
+
+    // Calls `edge` for every parent-child pair. Under the duplicate policy a
+    // short group at the end of a level reuses its last child, which is drawn
+    // as a separate dashed edge.
+    fn write_edges<W, F>(&self, out: &mut W, mut edge: F) -> fmt::Result
+    where
+        W: fmt::Write,
+        F: FnMut(&mut W, (usize, usize), (usize, usize), bool) -> fmt::Result,
+    {
+        for level in (1..self.height()).rev() {
+            let child_len = self.store.len(level - 1);
+
+            for index in 0..self.store.len(level) {
+                let first = index * self.arity;
+                let last = (first + self.arity).min(child_len);
+
+                for child in first..last {
+                    edge(out, (level, index), (level - 1, child), false)?;
+                }
+
+                if last - first < self.arity && self.policy == OddNodePolicy::Duplicate {
+                    edge(out, (level, index), (level - 1, last - 1), true)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn highlighted(&self, proof: Option<&MerkleProof>) -> (NodeSet, NodeSet) {
+        let mut path = HashSet::new();
+        let mut siblings = HashSet::new();
+
+        let proof = match proof {
+            Some(proof) if proof.leaf_index < self.leaf_count() => proof,
+            _ => return (path, siblings),
+        };
+
+        let mut index = proof.leaf_index;
+
+        for level in 0..self.height() {
+            path.insert((level, index));
+
+            let first = index - index % self.arity;
+            let last = (first + self.arity).min(self.store.len(level));
+            siblings.extend((first..last).filter(|&i| i != index).map(|i| (level, i)));
+
+            index /= self.arity;
+        }
+
+        (path, siblings)
+    }
+
+    fn highlight(
+        path: &NodeSet,
+        siblings: &NodeSet,
+        level: usize,
+        index: usize,
+    ) -> Highlight {
+        if path.contains(&(level, index)) {
+            Highlight::Path
+        } else if siblings.contains(&(level, index)) {
+            Highlight::Sibling
+        } else {
+            Highlight::None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merkle::builder::MerkleTreeBuilder;
+
+    fn sample_tree(count: usize) -> MerkleTree {
+        MerkleTree::new((0..count).map(|i| format!("Tx {}", i)).collect())
+    }
+
+    #[test]
+    fn test_dot_renders_every_node() {
+        let tree = sample_tree(5);
+        let dot = tree.to_dot(None);
+
+        assert!(dot.starts_with("digraph merkle {"));
+        assert!(dot.trim_end().ends_with('}'));
+
+        // 5 + 3 + 2 + 1 nodes, 10 edges, and a dashed edge for each odd level.
+        assert_eq!(dot.matches("[label=").count(), 11);
+        assert_eq!(dot.matches(" -> ").count(), 10 + 2);
+        assert_eq!(dot.matches("style=dashed").count(), 2);
+        assert!(dot.contains(&format!("n3_0 [label=\"L3 [0] {}\"]", &tree.root[..LABEL_LEN])));
+        assert!(!dot.contains("fillcolor"));
+    }
+
+    #[test]
+    fn test_dot_highlights_proof() {
+        let tree = sample_tree(8);
+        let proof = tree.get_proof(5).unwrap();
+        let dot = tree.to_dot(Some(&proof));
+
+        assert_eq!(dot.matches(PATH_COLOR).count(), 4);
+        assert_eq!(dot.matches(SIBLING_COLOR).count(), 3);
+        assert!(dot.contains(&format!("n0_5 [label=\"L0 [5] {}\", style=filled, fillcolor=\"{}\"]", &proof.leaf_hash[..LABEL_LEN], PATH_COLOR)));
+        assert!(dot.contains("n0_4 [label="));
+    }
+
+    #[test]
+    fn test_mermaid_output() {
+        let tree = MerkleTreeBuilder::new().arity(4).unwrap().build((0..6).map(|i| format!("Tx {}", i)).collect());
+        let proof = tree.get_proof(4).unwrap();
+        let mermaid = tree.to_mermaid(Some(&proof));
+
+        assert!(mermaid.starts_with("graph TD\n"));
+        assert_eq!(mermaid.matches(" --> ").count(), 6 + 2);
+        assert_eq!(mermaid.matches(" -.-> ").count(), 2);
+        assert!(mermaid.contains("  class n0_4,n1_1,n2_0 path;"));
+        assert!(mermaid.contains("  class n0_5,n1_0 sibling;"));
+    }
+
+    #[test]
+    fn test_io_writers_match_strings() {
+        let tree = sample_tree(6);
+        let proof = tree.get_proof(2).unwrap();
+
+        let mut dot = Vec::new();
+        tree.write_dot_to(&mut dot, Some(&proof)).unwrap();
+        assert_eq!(String::from_utf8(dot).unwrap(), tree.to_dot(Some(&proof)));
+
+        let mut mermaid = Vec::new();
+        tree.write_mermaid_to(&mut mermaid, None).unwrap();
+        assert_eq!(String::from_utf8(mermaid).unwrap(), tree.to_mermaid(None));
+    }
+
+    struct FullWriter;
+
+    impl io::Write for FullWriter {
+        fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+            Err(io::Error::new(io::ErrorKind::StorageFull, "disk full"))
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_io_writers_report_errors() {
+        let tree = sample_tree(3);
+
+        let error = tree.write_dot_to(&mut FullWriter, None).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::StorageFull);
+
+        let error = tree.write_mermaid_to(&mut FullWriter, None).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::StorageFull);
+    }
+
+    #[test]
+    fn test_empty_tree_renders() {
+        let tree = MerkleTree::new(Vec::new());
+
+        assert_eq!(tree.to_dot(None), "digraph merkle {\n  node [shape=box, fontname=\"monospace\"];\n}\n");
+        assert_eq!(tree.to_mermaid(None), "graph TD\n");
+    }
+}
@@ -0,0 +1,328 @@
+use crate::merkle::error::{MerkleError, ProofError};
+use crate::merkle::mode::HashMode;
+use crate::merkle::tree::{MerkleProof, MerkleTree, DEFAULT_ARITY};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortOrder {
+    Key,
+    Digest,
+}
+
+#[derive(Debug, Clone)]
+pub struct SortedMerkleTree {
+    pub tree: MerkleTree,
+    pub keys: Vec<String>,
+    pub order: SortOrder,
+}
+
+#[derive(Debug, Clone)]
+pub struct Neighbour {
+    pub key: String,
+    pub proof: MerkleProof,
+}
+
+#[derive(Debug, Clone)]
+pub struct ExclusionProof {
+    pub left: Option<Neighbour>,
+    pub right: Option<Neighbour>,
+}
+
+impl SortedMerkleTree {
+
+    pub fn new(keys: Vec<String>, order: SortOrder) -> Self {
+        let mut keys = keys;
+
+        match order {
+            SortOrder::Key => keys.sort(),
+            SortOrder::Digest => keys.sort_by_cached_key(|key| MerkleTree::hash_leaf(key.as_bytes())),
+        }
+        keys.dedup();
+
+        SortedMerkleTree {
+            tree: MerkleTree::new(keys.clone()),
+            keys,
+            order,
+        }
+    }
+
+    pub fn root(&self) -> &str {
+        &self.tree.root
+    }
+
+    // Published next to the root; exclusion proofs are checked against it.
+    pub fn leaf_count(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn get_proof(&self, key: &str) -> Result<MerkleProof, MerkleError> {
+        let index = self
+            .search(key)
+            .map_err(|_| MerkleError::KeyNotFound(key.to_string()))?;
+        self.tree.get_proof(index)
+    }
+
+    pub fn get_exclusion_proof(&self, key: &str) -> Result<ExclusionProof, MerkleError> {
+        if self.keys.is_empty() {
+            return Err(MerkleError::EmptyTree);
+        }
+
+        let index = match self.search(key) {
+            Ok(_) => return Err(MerkleError::KeyPresent(key.to_string())),
+            Err(index) => index,
+        };
+
+        let left = index.checked_sub(1).map(|i| self.neighbour(i)).transpose()?;
+        let right = if index < self.keys.len() { Some(self.neighbour(index)?) } else { None };
+
+        Ok(ExclusionProof { left, right })
+    }
+
+    fn search(&self, key: &str) -> Result<usize, usize> {
+        match self.order {
+            SortOrder::Key => self.keys.binary_search_by(|probe| probe.as_str().cmp(key)),
+            SortOrder::Digest => {
+                let digest = MerkleTree::hash_leaf(key.as_bytes());
+                self.keys
+                    .binary_search_by(|probe| MerkleTree::hash_leaf(probe.as_bytes()).cmp(&digest))
+            }
+        }
+    }
+
+    fn neighbour(&self, index: usize) -> Result<Neighbour, MerkleError> {
+        Ok(Neighbour {
+            key: self.keys[index].clone(),
+            proof: self.tree.get_proof(index)?,
+        })
+    }
+}
+
+impl Neighbour {
+
+    // Pins the proof to a full-depth path for its `leaf_index`, so the index
+    // is the neighbour's real position and adjacency can be read from it.
+    fn check(&self, root: &str, leaf_count: usize) -> Result<(), ProofError> {
+        self.proof.check_shape(DEFAULT_ARITY, HashMode::Sha512, None)?;
+        self.proof.check_duplicated_path(leaf_count)?;
+
+        let leaf_hash = MerkleTree::hash_leaf(self.key.as_bytes());
+        if leaf_hash != self.proof.leaf_hash {
+            return Err(ProofError::LeafMismatch {
+                expected: self.proof.leaf_hash.clone(),
+                actual: leaf_hash,
+            });
+        }
+
+        self.proof.verify(root)
+    }
+
+    fn sort_key(&self, order: SortOrder) -> &str {
+        match order {
+            SortOrder::Key => &self.key,
+            SortOrder::Digest => &self.proof.leaf_hash,
+        }
+    }
+}
+
+impl ExclusionProof {
+
+    // `leaf_count` is the published size of the tree behind `root`.
+    pub fn verify(&self, root: &str, leaf_count: usize, key: &str, order: SortOrder) -> Result<(), ProofError> {
+        let digest = MerkleTree::hash_leaf(key.as_bytes());
+        let target = match order {
+            SortOrder::Key => key,
+            SortOrder::Digest => digest.as_str(),
+        };
+
+        let (adjacent, excluded) = match (&self.left, &self.right) {
+            (Some(left), Some(right)) => {
+                left.check(root, leaf_count)?;
+                right.check(root, leaf_count)?;
+                (
+                    right.proof.leaf_index == left.proof.leaf_index + 1,
+                    left.sort_key(order) < target && target < right.sort_key(order),
+                )
+            }
+            (Some(left), None) => {
+                left.check(root, leaf_count)?;
+                (left.proof.leaf_index + 1 == leaf_count, left.sort_key(order) < target)
+            }
+            (None, Some(right)) => {
+                right.check(root, leaf_count)?;
+                (right.proof.leaf_index == 0, target < right.sort_key(order))
+            }
+            (None, None) => (false, false),
+        };
+
+        if !adjacent {
+            return Err(ProofError::InvalidNeighbours);
+        }
+        if !excluded {
+            return Err(ProofError::KeyNotExcluded(key.to_string()));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn deny_list(order: SortOrder) -> SortedMerkleTree {
+        let keys = ["cert-17", "cert-03", "cert-42", "cert-88", "cert-29"]
+            .iter()
+            .map(|key| key.to_string())
+            .collect();
+
+        SortedMerkleTree::new(keys, order)
+    }
+
+    #[test]
+    fn test_sorted_tree_orders_keys() {
+        let tree = deny_list(SortOrder::Key);
+
+        assert_eq!(tree.keys, vec!["cert-03", "cert-17", "cert-29", "cert-42", "cert-88"]);
+
+        let tree = deny_list(SortOrder::Digest);
+        assert!(tree.tree.store.levels[0].windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn test_membership_proof() {
+        for order in [SortOrder::Key, SortOrder::Digest] {
+            let tree = deny_list(order);
+            let proof = tree.get_proof("cert-42").expect("Key should be present");
+
+            assert!(tree.tree.verify_proof(&proof).is_ok());
+            assert_eq!(
+                tree.get_exclusion_proof("cert-42").unwrap_err(),
+                MerkleError::KeyPresent("cert-42".to_string())
+            );
+        }
+
+        let empty = SortedMerkleTree::new(Vec::new(), SortOrder::Key);
+        assert_eq!(empty.get_exclusion_proof("cert-42").unwrap_err(), MerkleError::EmptyTree);
+    }
+
+    #[test]
+    fn test_exclusion_proofs_verify() {
+        for order in [SortOrder::Key, SortOrder::Digest] {
+            let tree = deny_list(order);
+
+            for missing in ["cert-00", "cert-20", "cert-50", "cert-99", "other"] {
+                let proof = tree
+                    .get_exclusion_proof(missing)
+                    .expect("Should generate exclusion proof");
+
+                assert_eq!(
+                    proof.verify(tree.root(), tree.leaf_count(), missing, order),
+                    Ok(()),
+                    "Exclusion of {} should verify under {:?}",
+                    missing,
+                    order
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_exclusion_proof_edges() {
+        let tree = deny_list(SortOrder::Key);
+
+        let below = tree.get_exclusion_proof("cert-00").unwrap();
+        assert!(below.left.is_none());
+        assert!(below.right.is_some());
+
+        let above = tree.get_exclusion_proof("cert-99").unwrap();
+        assert!(above.left.is_some());
+        assert!(above.right.is_none());
+    }
+
+    #[test]
+    fn test_forged_exclusion_proof_detection() {
+        let tree = deny_list(SortOrder::Key);
+
+        // Bracketing a present key with non-adjacent leaves must fail.
+        let forged = ExclusionProof {
+            left: Some(tree.neighbour(1).unwrap()),
+            right: Some(tree.neighbour(3).unwrap()),
+        };
+        assert_eq!(
+            forged.verify(tree.root(), tree.leaf_count(), "cert-29", SortOrder::Key),
+            Err(ProofError::InvalidNeighbours)
+        );
+
+        // Claiming a middle leaf is the last one must fail.
+        let forged = ExclusionProof {
+            left: Some(tree.neighbour(3).unwrap()),
+            right: None,
+        };
+        assert_eq!(
+            forged.verify(tree.root(), tree.leaf_count(), "cert-50", SortOrder::Key),
+            Err(ProofError::InvalidNeighbours)
+        );
+
+        // Adjacent neighbours that do not bracket the key must fail.
+        let proof = tree.get_exclusion_proof("cert-20").unwrap();
+        assert_eq!(
+            proof.verify(tree.root(), tree.leaf_count(), "cert-30", SortOrder::Key),
+            Err(ProofError::KeyNotExcluded("cert-30".to_string()))
+        );
+
+        let mut proof = tree.get_exclusion_proof("cert-20").unwrap();
+        proof.left.as_mut().unwrap().key = "cert-19".to_string();
+        assert!(matches!(
+            proof.verify(tree.root(), tree.leaf_count(), "cert-20", SortOrder::Key),
+            Err(ProofError::LeafMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_inner_node_neighbour_rejected() {
+        let tree = deny_list(SortOrder::Key);
+        let leaves = &tree.tree.store.levels[0];
+
+        // The parent of the first two leaves is the SHA512 of their joined
+        // hashes, so that text passes as a key one level up the tree.
+        let mut proof = tree.neighbour(0).unwrap().proof;
+        let inner = format!("{}{}", leaves[0], leaves[1]);
+        proof.leaf_hash = MerkleTree::hash_leaf(inner.as_bytes());
+        proof.proof_path.remove(0);
+        assert_eq!(proof.compute_root().as_deref(), Ok(tree.root()));
+
+        let forged = ExclusionProof {
+            left: None,
+            right: Some(Neighbour { key: inner.clone(), proof }),
+        };
+        assert_eq!(
+            forged.verify(tree.root(), tree.leaf_count(), "0", SortOrder::Key),
+            Err(ProofError::DepthMismatch { expected: 3, actual: 2 })
+        );
+
+        // Relabelling a neighbour's index breaks the path it must follow.
+        let mut proof = tree.get_exclusion_proof("cert-50").unwrap();
+        proof.left.as_mut().unwrap().proof.leaf_index = 4;
+        assert_eq!(
+            proof.verify(tree.root(), tree.leaf_count(), "cert-50", SortOrder::Key),
+            Err(ProofError::PathMismatch { index: 4, leaf_count: 5 })
+        );
+    }
+
+    #[test]
+    fn test_foreign_proof_shape_rejected() {
+        let tree = deny_list(SortOrder::Key);
+        let mut proof = tree.get_exclusion_proof("cert-20").unwrap();
+
+        let left = proof.left.as_mut().unwrap();
+        left.proof.mode = HashMode::Bitcoin;
+        left.proof.proof_path[0].hash = "zz".to_string();
+
+        assert_eq!(
+            proof.verify(tree.root(), tree.leaf_count(), "cert-20", SortOrder::Key),
+            Err(ProofError::HashModeMismatch {
+                expected: HashMode::Sha512,
+                actual: HashMode::Bitcoin,
+            })
+        );
+    }
+}
@@ -0,0 +1,297 @@
+use crate::hash::sha512::SHA512;
+use crate::merkle::error::ProofError;
+use crate::merkle::tree::MerkleTree;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::OnceLock;
+
+pub const KEY_BITS: usize = 256;
+
+pub type Key = [u8; 32];
+
+// Hash of each empty subtree, indexed by height. Height 0 is an empty leaf,
+// which uses a value no SHA-512 leaf hash can realistically take.
+fn default_hashes() -> &'static [String] {
+    static DEFAULTS: OnceLock<Vec<String>> = OnceLock::new();
+
+    DEFAULTS.get_or_init(|| {
+        let mut defaults = vec!["0".repeat(128)];
+
+        for height in 0..KEY_BITS {
+            let child = &defaults[height];
+            defaults.push(MerkleTree::hash_pair(child, child));
+        }
+
+        defaults
+    })
+}
+
+fn bit(key: &Key, height: usize) -> bool {
+    let index = KEY_BITS - 1 - height;
+    (key[index / 8] >> (7 - index % 8)) & 1 == 1
+}
+
+fn prefix(key: &Key, height: usize) -> Key {
+    let mut prefix = *key;
+
+    for h in 0..height {
+        let index = KEY_BITS - 1 - h;
+        prefix[index / 8] &= !(1 << (7 - index % 8));
+    }
+
+    prefix
+}
+
+fn sibling_prefix(key: &Key, height: usize) -> Key {
+    let mut sibling = prefix(key, height);
+    let index = KEY_BITS - 1 - height;
+    sibling[index / 8] ^= 1 << (7 - index % 8);
+    sibling
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SparseMerkleTree {
+    leaves: BTreeMap<Key, String>,
+    nodes: HashMap<(usize, Key), String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SparseProof {
+    pub key: Key,
+    pub value: Option<String>,
+    pub bitmap: [u8; 32],
+    pub siblings: Vec<String>,
+}
+
+impl SparseMerkleTree {
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn key_for(data: &[u8]) -> Key {
+        let mut key = [0u8; 32];
+        key.copy_from_slice(&SHA512::hash(data)[..32]);
+        key
+    }
+
+    pub fn root(&self) -> String {
+        self.node(KEY_BITS, &[0u8; 32])
+    }
+
+    pub fn len(&self) -> usize {
+        self.leaves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.leaves.is_empty()
+    }
+
+    pub fn get(&self, key: &Key) -> Option<&String> {
+        self.leaves.get(key)
+    }
+
+    pub fn insert(&mut self, key: Key, value: String) -> Option<String> {
+        self.set_node(0, key, MerkleTree::hash_leaf(value.as_bytes()));
+        self.update_path(&key);
+        self.leaves.insert(key, value)
+    }
+
+    pub fn remove(&mut self, key: &Key) -> Option<String> {
+        let previous = self.leaves.remove(key)?;
+        self.set_node(0, *key, default_hashes()[0].clone());
+        self.update_path(key);
+        Some(previous)
+    }
+
+    pub fn prove(&self, key: &Key) -> SparseProof {
+        let mut bitmap = [0u8; 32];
+        let mut siblings = Vec::new();
+
+        for height in 0..KEY_BITS {
+            if let Some(sibling) = self.nodes.get(&(height, sibling_prefix(key, height))) {
+                bitmap[height / 8] |= 1 << (height % 8);
+                siblings.push(sibling.clone());
+            }
+        }
+
+        SparseProof {
+            key: *key,
+            value: self.leaves.get(key).cloned(),
+            bitmap,
+            siblings,
+        }
+    }
+
+    fn node(&self, height: usize, prefix: &Key) -> String {
+        self.nodes
+            .get(&(height, *prefix))
+            .cloned()
+            .unwrap_or_else(|| default_hashes()[height].clone())
+    }
+
+    // Only non-empty subtrees are stored; empty ones fall back to the defaults.
+    fn set_node(&mut self, height: usize, prefix: Key, hash: String) {
+        if hash == default_hashes()[height] {
+            self.nodes.remove(&(height, prefix));
+        } else {
+            self.nodes.insert((height, prefix), hash);
+        }
+    }
+
+    fn update_path(&mut self, key: &Key) {
+        for height in 0..KEY_BITS {
+            let current = self.node(height, &prefix(key, height));
+            let sibling = self.node(height, &sibling_prefix(key, height));
+
+            let parent = if bit(key, height) {
+                MerkleTree::hash_pair(&sibling, &current)
+            } else {
+                MerkleTree::hash_pair(&current, &sibling)
+            };
+
+            self.set_node(height + 1, prefix(key, height + 1), parent);
+        }
+    }
+}
+
+impl SparseProof {
+
+    pub fn is_membership(&self) -> bool {
+        self.value.is_some()
+    }
+
+    pub fn verify(&self, root: &str) -> Result<(), ProofError> {
+        let defaults = default_hashes();
+        let expected = self.bitmap.iter().map(|byte| byte.count_ones() as usize).sum();
+        if expected != self.siblings.len() {
+            return Err(ProofError::HashCountMismatch {
+                expected,
+                actual: self.siblings.len(),
+            });
+        }
+
+        let mut siblings = self.siblings.iter();
+
+        let mut current_hash = match &self.value {
+            Some(value) => MerkleTree::hash_leaf(value.as_bytes()),
+            None => defaults[0].clone(),
+        };
+
+        for (height, default) in defaults.iter().enumerate().take(KEY_BITS) {
+            let sibling = if self.bitmap[height / 8] & (1 << (height % 8)) != 0 {
+                siblings.next().expect("Sibling count matches the bitmap")
+            } else {
+                default
+            };
+
+            current_hash = if bit(&self.key, height) {
+                MerkleTree::hash_pair(sibling, &current_hash)
+            } else {
+                MerkleTree::hash_pair(&current_hash, sibling)
+            };
+        }
+
+        if current_hash != root {
+            return Err(ProofError::RootMismatch {
+                expected: root.to_string(),
+                actual: current_hash,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_tree() -> SparseMerkleTree {
+        let mut tree = SparseMerkleTree::new();
+
+        for name in ["alice", "bob", "carol", "dave"] {
+            tree.insert(SparseMerkleTree::key_for(name.as_bytes()), format!("{} balance", name));
+        }
+
+        tree
+    }
+
+    #[test]
+    fn test_insert_get_remove() {
+        let mut tree = SparseMerkleTree::new();
+        let empty_root = tree.root();
+        let key = SparseMerkleTree::key_for(b"alice");
+
+        assert!(tree.insert(key, "10".to_string()).is_none());
+        assert_eq!(tree.get(&key), Some(&"10".to_string()));
+        assert_eq!(tree.insert(key, "20".to_string()), Some("10".to_string()));
+        assert_ne!(tree.root(), empty_root);
+
+        assert_eq!(tree.remove(&key), Some("20".to_string()));
+        assert!(tree.get(&key).is_none());
+        assert!(tree.is_empty());
+        assert_eq!(tree.root(), empty_root, "Removing every key should restore the empty root");
+    }
+
+    #[test]
+    fn test_root_independent_of_insertion_order() {
+        let tree = sample_tree();
+
+        let mut reversed = SparseMerkleTree::new();
+        for name in ["dave", "carol", "bob", "alice"] {
+            reversed.insert(SparseMerkleTree::key_for(name.as_bytes()), format!("{} balance", name));
+        }
+
+        assert_eq!(tree.root(), reversed.root());
+    }
+
+    #[test]
+    fn test_membership_proof() {
+        let tree = sample_tree();
+        let proof = tree.prove(&SparseMerkleTree::key_for(b"carol"));
+
+        assert!(proof.is_membership());
+        assert_eq!(proof.verify(&tree.root()), Ok(()));
+        assert!(proof.siblings.len() < 16, "Empty siblings should be compressed out");
+    }
+
+    #[test]
+    fn test_non_membership_proof() {
+        let tree = sample_tree();
+        let proof = tree.prove(&SparseMerkleTree::key_for(b"mallory"));
+
+        assert!(!proof.is_membership());
+        assert_eq!(proof.verify(&tree.root()), Ok(()));
+    }
+
+    #[test]
+    fn test_invalid_sparse_proof_detection() {
+        let tree = sample_tree();
+        let key = SparseMerkleTree::key_for(b"bob");
+
+        let mut forged = tree.prove(&key);
+        forged.value = None;
+        assert!(
+            matches!(forged.verify(&tree.root()), Err(ProofError::RootMismatch { .. })),
+            "Hiding a present key should fail"
+        );
+
+        let mut forged = tree.prove(&key);
+        forged.value = Some("stolen".to_string());
+        assert!(
+            matches!(forged.verify(&tree.root()), Err(ProofError::RootMismatch { .. })),
+            "Wrong value should fail"
+        );
+
+        let mut forged = tree.prove(&key);
+        forged.siblings.pop();
+        assert_eq!(
+            forged.verify(&tree.root()),
+            Err(ProofError::HashCountMismatch {
+                expected: forged.siblings.len() + 1,
+                actual: forged.siblings.len(),
+            }),
+            "Truncated proof should fail"
+        );
+    }
+}
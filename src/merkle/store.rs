@@ -0,0 +1,344 @@
+use crate::merkle::encoding::HASH_SIZE;
+use crate::merkle::error::{MerkleError, ProofError};
+use crate::merkle::mode::HashMode;
+use crate::merkle::tree::{MerkleProof, MerkleTree, ProofElement, DEFAULT_ARITY};
+use memmap2::Mmap;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+pub const STORE_MAGIC: [u8; 4] = *b"MKLT";
+pub const STORE_VERSION: u8 = 1;
+
+// Header layout: magic (4) | version (1) | reserved (3) | leaf count (u64 BE)
+// followed by every level from the leaves up, each node a raw 64-byte hash.
+const HEADER_SIZE: usize = 16;
+
+fn level_lengths(leaf_count: u64) -> Vec<u64> {
+    let mut lengths = Vec::new();
+
+    if leaf_count == 0 {
+        return lengths;
+    }
+
+    let mut len = leaf_count;
+    lengths.push(len);
+
+    while len > 1 {
+        len = len.div_ceil(2);
+        lengths.push(len);
+    }
+
+    lengths
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+#[derive(Debug)]
+pub struct DiskMerkleTree {
+    mmap: Mmap,
+    leaf_count: usize,
+    level_offsets: Vec<usize>,
+    level_lengths: Vec<usize>,
+}
+
+impl DiskMerkleTree {
+
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let file = File::open(path)?;
+
+        // SAFETY: the map is read-only and the file format is append-only; the
+        // node file must not be truncated by another process while it is open.
+        let mmap = unsafe { Mmap::map(&file)? };
+
+        if mmap.len() < HEADER_SIZE || mmap[..4] != STORE_MAGIC {
+            return Err(invalid_data("not a Merkle tree node file"));
+        }
+
+        if mmap[4] != STORE_VERSION {
+            return Err(invalid_data("unsupported node file version"));
+        }
+
+        let mut count = [0u8; 8];
+        count.copy_from_slice(&mmap[8..HEADER_SIZE]);
+        let leaf_count = u64::from_be_bytes(count);
+
+        let lengths = level_lengths(leaf_count);
+        let expected = lengths
+            .iter()
+            .try_fold(HEADER_SIZE as u64, |total, &len| {
+                len.checked_mul(HASH_SIZE as u64).and_then(|size| total.checked_add(size))
+            })
+            .ok_or_else(|| invalid_data("leaf count overflows the file size"))?;
+
+        if mmap.len() as u64 != expected {
+            return Err(invalid_data("node file length does not match its header"));
+        }
+
+        let mut level_offsets = Vec::with_capacity(lengths.len());
+        let mut offset = HEADER_SIZE;
+        for &len in &lengths {
+            level_offsets.push(offset);
+            offset += len as usize * HASH_SIZE;
+        }
+
+        Ok(DiskMerkleTree {
+            mmap,
+            leaf_count: leaf_count as usize,
+            level_offsets,
+            level_lengths: lengths.into_iter().map(|len| len as usize).collect(),
+        })
+    }
+
+    pub fn leaf_count(&self) -> usize {
+        self.leaf_count
+    }
+
+    pub fn height(&self) -> usize {
+        self.level_lengths.len()
+    }
+
+    pub fn node(&self, level: usize, index: usize) -> Option<String> {
+        if index >= *self.level_lengths.get(level)? {
+            return None;
+        }
+
+        let start = self.level_offsets[level] + index * HASH_SIZE;
+        Some(hex::encode(&self.mmap[start..start + HASH_SIZE]))
+    }
+
+    pub fn root(&self) -> String {
+        self.height()
+            .checked_sub(1)
+            .and_then(|top| self.node(top, 0))
+            .unwrap_or_default()
+    }
+
+    pub fn get_proof(&self, leaf_index: usize) -> Result<MerkleProof, MerkleError> {
+        if leaf_index >= self.leaf_count {
+            return Err(MerkleError::IndexOutOfRange {
+                index: leaf_index,
+                leaf_count: self.leaf_count,
+            });
+        }
+
+        let mut proof_path = Vec::new();
+        let mut current_index = leaf_index;
+
+        for level in 0..self.height() - 1 {
+            let sibling_index = current_index ^ 1;
+
+            let sibling_hash = self
+                .node(level, sibling_index)
+                .or_else(|| self.node(level, current_index))
+                .ok_or(MerkleError::MissingNode { level, index: sibling_index })?;
+
+            proof_path.push(ProofElement {
+                hash: sibling_hash,
+                is_right: sibling_index > current_index,
+            });
+
+            current_index /= 2;
+        }
+
+        Ok(MerkleProof {
+            leaf_index,
+            leaf_hash: self.node(0, leaf_index).ok_or(MerkleError::MissingNode { level: 0, index: leaf_index })?,
+            arity: DEFAULT_ARITY,
+            mode: HashMode::Sha512,
+            proof_path,
+        })
+    }
+
+    pub fn verify_proof(&self, proof: &MerkleProof) -> Result<(), ProofError> {
+        proof.check_shape(DEFAULT_ARITY, HashMode::Sha512, Some(self.height().saturating_sub(1)))?;
+        proof.check_leaf(self.node(0, proof.leaf_index), self.leaf_count)?;
+        proof.verify(&self.root())
+    }
+}
+
+// Leaves are streamed straight to disk; each upper level is then built by
+// reading the level below back from the file, so memory stays bounded by the
+// I/O buffers regardless of the number of leaves.
+pub struct DiskMerkleTreeBuilder {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    leaf_count: u64,
+}
+
+impl DiskMerkleTreeBuilder {
+
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path.as_ref())?;
+
+        let mut writer = BufWriter::new(file);
+        writer.write_all(&[0u8; HEADER_SIZE])?;
+
+        Ok(DiskMerkleTreeBuilder {
+            path: path.as_ref().to_path_buf(),
+            writer,
+            leaf_count: 0,
+        })
+    }
+
+    pub fn push(&mut self, leaf: &[u8]) -> io::Result<()> {
+        let leaf_hash = MerkleTree::hash_leaf(leaf);
+        self.write_hash(&leaf_hash)?;
+        self.leaf_count += 1;
+        Ok(())
+    }
+
+    pub fn extend<I, T>(&mut self, leaves: I) -> io::Result<()>
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<[u8]>,
+    {
+        for leaf in leaves {
+            self.push(leaf.as_ref())?;
+        }
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> io::Result<DiskMerkleTree> {
+        let lengths = level_lengths(self.leaf_count);
+        let mut level_offset = HEADER_SIZE as u64;
+
+        for window in lengths.windows(2) {
+            self.writer.flush()?;
+
+            let mut reader = BufReader::new(File::open(&self.path)?);
+            reader.seek(SeekFrom::Start(level_offset))?;
+
+            let mut left = [0u8; HASH_SIZE];
+            let mut right = [0u8; HASH_SIZE];
+
+            for pair in 0..window[1] {
+                reader.read_exact(&mut left)?;
+
+                let right = if pair * 2 + 1 < window[0] {
+                    reader.read_exact(&mut right)?;
+                    &right
+                } else {
+                    &left
+                };
+
+                let parent = MerkleTree::hash_pair(&hex::encode(left), &hex::encode(right));
+                self.write_hash(&parent)?;
+            }
+
+            level_offset += window[0] * HASH_SIZE as u64;
+        }
+
+        let mut header = [0u8; HEADER_SIZE];
+        header[..4].copy_from_slice(&STORE_MAGIC);
+        header[4] = STORE_VERSION;
+        header[8..].copy_from_slice(&self.leaf_count.to_be_bytes());
+
+        self.writer.seek(SeekFrom::Start(0))?;
+        self.writer.write_all(&header)?;
+        self.writer.flush()?;
+        self.writer.get_ref().sync_all()?;
+
+        DiskMerkleTree::open(&self.path)
+    }
+
+    fn write_hash(&mut self, hash: &str) -> io::Result<()> {
+        let bytes = hex::decode(hash).map_err(|_| invalid_data("node hash is not valid hex"))?;
+        self.writer.write_all(&bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("merkletree-{}-{}.bin", name, std::process::id()))
+    }
+
+    fn leaves(count: usize) -> Vec<String> {
+        (0..count).map(|i| format!("Record {}", i)).collect()
+    }
+
+    #[test]
+    fn test_disk_tree_matches_memory_tree() {
+        for count in [1, 2, 7, 64, 333] {
+            let path = temp_path(&format!("match-{}", count));
+
+            let mut builder = DiskMerkleTreeBuilder::create(&path).expect("Should create node file");
+            builder.extend(leaves(count)).unwrap();
+            let disk = builder.finish().expect("Should build node file");
+
+            let memory = MerkleTree::new(leaves(count));
+
+            assert_eq!(disk.leaf_count(), count);
+            assert_eq!(disk.height(), memory.height());
+            assert_eq!(disk.root(), memory.root);
+
+            for i in [0, count / 2, count - 1] {
+                let proof = disk.get_proof(i).expect("Should generate proof");
+                assert_eq!(Ok(&proof), memory.get_proof(i).as_ref());
+                assert!(disk.verify_proof(&proof).is_ok());
+            }
+
+            std::fs::remove_file(&path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_reopen_node_file() {
+        let path = temp_path("reopen");
+
+        let mut builder = DiskMerkleTreeBuilder::create(&path).unwrap();
+        builder.extend(leaves(10)).unwrap();
+        let root = builder.finish().unwrap().root();
+
+        let reopened = DiskMerkleTree::open(&path).expect("Should reopen node file");
+        assert_eq!(reopened.root(), root);
+        assert_eq!(
+            reopened.get_proof(10),
+            Err(MerkleError::IndexOutOfRange { index: 10, leaf_count: 10 })
+        );
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_empty_disk_tree() {
+        let path = temp_path("empty");
+
+        let disk = DiskMerkleTreeBuilder::create(&path).unwrap().finish().unwrap();
+        assert_eq!(disk.leaf_count(), 0);
+        assert_eq!(disk.root(), "");
+        assert!(disk.get_proof(0).is_err());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_corrupt_node_file_rejected() {
+        let path = temp_path("corrupt");
+
+        let mut builder = DiskMerkleTreeBuilder::create(&path).unwrap();
+        builder.extend(leaves(5)).unwrap();
+        builder.finish().unwrap();
+
+        let mut bytes = std::fs::read(&path).unwrap();
+        bytes.pop();
+        std::fs::write(&path, &bytes).unwrap();
+        assert_eq!(DiskMerkleTree::open(&path).unwrap_err().kind(), io::ErrorKind::InvalidData);
+
+        bytes[0] = b'X';
+        std::fs::write(&path, &bytes).unwrap();
+        assert_eq!(DiskMerkleTree::open(&path).unwrap_err().kind(), io::ErrorKind::InvalidData);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
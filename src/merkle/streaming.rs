@@ -0,0 +1,198 @@
+use crate::merkle::tree::MerkleTree;
+use std::io::{self, Read};
+
+// Computes the same root as `MerkleTree::new` while holding at most one
+// pending hash per level, so memory grows with log2 of the leaf count.
+#[derive(Debug, Clone, Default)]
+pub struct MerkleRootBuilder {
+    pending: Vec<Option<String>>,
+    leaf_count: usize,
+}
+
+impl MerkleRootBuilder {
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn leaf_count(&self) -> usize {
+        self.leaf_count
+    }
+
+    pub fn push<T: AsRef<[u8]>>(&mut self, leaf: T) {
+        let mut carry = MerkleTree::hash_leaf(leaf.as_ref());
+        self.leaf_count += 1;
+
+        for slot in self.pending.iter_mut() {
+            match slot.take() {
+                Some(left) => carry = MerkleTree::hash_pair(&left, &carry),
+                None => {
+                    *slot = Some(carry);
+                    return;
+                }
+            }
+        }
+
+        self.pending.push(Some(carry));
+    }
+
+    pub fn extend<I, T>(&mut self, leaves: I)
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<[u8]>,
+    {
+        for leaf in leaves {
+            self.push(leaf);
+        }
+    }
+
+    // Reads records prefixed with their length as a big-endian u32 until the
+    // reader is exhausted, returning the number of records pushed.
+    pub fn read_records<R: Read>(&mut self, mut reader: R) -> io::Result<usize> {
+        let mut count = 0;
+
+        loop {
+            let mut prefix = [0u8; 4];
+            let mut filled = 0;
+
+            while filled < prefix.len() {
+                match reader.read(&mut prefix[filled..]) {
+                    Ok(0) if filled == 0 => return Ok(count),
+                    Ok(0) => return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "truncated record length")),
+                    Ok(n) => filled += n,
+                    Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                    Err(err) => return Err(err),
+                }
+            }
+
+            let len = u32::from_be_bytes(prefix) as u64;
+            let mut record = Vec::new();
+            (&mut reader).take(len).read_to_end(&mut record)?;
+
+            if record.len() as u64 != len {
+                return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "truncated record"));
+            }
+
+            self.push(&record);
+            count += 1;
+        }
+    }
+
+    pub fn finish(mut self) -> String {
+        let mut node: Option<String> = None;
+
+        for level in 0..self.pending.len() {
+            let current = match (self.pending[level].take(), node.take()) {
+                (Some(left), Some(right)) => {
+                    node = Some(MerkleTree::hash_pair(&left, &right));
+                    continue;
+                }
+                (Some(current), None) | (None, Some(current)) => current,
+                (None, None) => continue,
+            };
+
+            if self.pending[level + 1..].iter().all(Option::is_none) {
+                return current;
+            }
+
+            // The last node of an odd level is paired with itself.
+            node = Some(MerkleTree::hash_pair(&current, &current));
+        }
+
+        node.unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn records(count: usize) -> Vec<String> {
+        (0..count).map(|i| format!("Record {}", i)).collect()
+    }
+
+    #[test]
+    fn test_streaming_root_matches_tree() {
+        for count in 0..=70 {
+            let mut builder = MerkleRootBuilder::new();
+            builder.extend(records(count));
+
+            assert_eq!(builder.leaf_count(), count);
+            assert_eq!(builder.finish(), MerkleTree::new(records(count)).root, "Mismatch for {} leaves", count);
+        }
+    }
+
+    #[test]
+    fn test_pending_hashes_stay_logarithmic() {
+        let mut builder = MerkleRootBuilder::new();
+        builder.extend(records(1000));
+
+        assert_eq!(builder.pending.len(), 10);
+        assert!(builder.pending.iter().flatten().count() <= 10);
+    }
+
+    #[test]
+    fn test_read_length_prefixed_records() {
+        let mut bytes = Vec::new();
+        for record in records(13) {
+            bytes.extend_from_slice(&(record.len() as u32).to_be_bytes());
+            bytes.extend_from_slice(record.as_bytes());
+        }
+
+        let mut builder = MerkleRootBuilder::new();
+        let count = builder.read_records(bytes.as_slice()).expect("Should read records");
+
+        assert_eq!(count, 13);
+        assert_eq!(builder.finish(), MerkleTree::new(records(13)).root);
+    }
+
+    #[test]
+    fn test_truncated_records_rejected() {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&10u32.to_be_bytes());
+        bytes.extend_from_slice(b"short");
+
+        let error = MerkleRootBuilder::new().read_records(bytes.as_slice()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+
+        let error = MerkleRootBuilder::new().read_records(&[0u8, 0][..]).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    // Hands out one byte per read and is interrupted before every other one.
+    struct Flaky<'a> {
+        bytes: &'a [u8],
+        interrupt: bool,
+    }
+
+    impl Read for Flaky<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.interrupt = !self.interrupt;
+            if self.interrupt {
+                return Err(io::Error::from(io::ErrorKind::Interrupted));
+            }
+
+            let Some((first, rest)) = self.bytes.split_first() else {
+                return Ok(0);
+            };
+            buf[0] = *first;
+            self.bytes = rest;
+            Ok(1)
+        }
+    }
+
+    #[test]
+    fn test_interrupted_reads_are_retried() {
+        let mut bytes = Vec::new();
+        for record in records(5) {
+            bytes.extend_from_slice(&(record.len() as u32).to_be_bytes());
+            bytes.extend_from_slice(record.as_bytes());
+        }
+
+        let mut builder = MerkleRootBuilder::new();
+        let reader = Flaky { bytes: &bytes, interrupt: false };
+
+        assert_eq!(builder.read_records(reader).unwrap(), 5);
+        assert_eq!(builder.finish(), MerkleTree::new(records(5)).root);
+    }
+}
@@ -0,0 +1,368 @@
+use crate::hash::sha512::SHA512;
+use crate::merkle::error::{MerkleError, ProofError};
+use crate::merkle::mode::HashMode;
+use crate::merkle::tree::promoted_directions;
+use serde::{Deserialize, Serialize};
+
+const LEAF_PREFIX: u8 = 0x00;
+const NODE_PREFIX: u8 = 0x01;
+
+// Sums are unsigned, so a negative balance cannot be encoded in a tree or a
+// proof, and every addition is checked so none can wrap around either.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SumNode {
+    pub hash: String,
+    pub sum: u64,
+}
+
+impl SumNode {
+
+    // `id` is whatever the custodian publishes for the account, typically a
+    // salted hash of the customer id so that neighbours learn nothing.
+    pub fn leaf(id: &str, balance: u64) -> Self {
+        let mut bytes = vec![LEAF_PREFIX];
+        bytes.extend_from_slice(&balance.to_be_bytes());
+        bytes.extend_from_slice(id.as_bytes());
+
+        SumNode {
+            hash: hex::encode(SHA512::hash(&bytes)),
+            sum: balance,
+        }
+    }
+
+    // Both hashes and both sums are committed to in the parent hash with a
+    // fixed width. Returns `None` if the sum overflows or a child hash is not
+    // 64 bytes of lowercase hex.
+    pub fn parent(left: &SumNode, right: &SumNode) -> Option<SumNode> {
+        let sum = left.sum.checked_add(right.sum)?;
+
+        let mut bytes = vec![NODE_PREFIX];
+        for child in [left, right] {
+            if !HashMode::Sha512.is_valid_hash(&child.hash) {
+                return None;
+            }
+            bytes.extend_from_slice(&hex::decode(&child.hash).ok()?);
+            bytes.extend_from_slice(&child.sum.to_be_bytes());
+        }
+
+        Some(SumNode {
+            hash: hex::encode(SHA512::hash(&bytes)),
+            sum,
+        })
+    }
+}
+
+// A lone node at the end of a level is carried up unchanged; duplicating it
+// would count its balance twice.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MerkleSumTree {
+    pub levels: Vec<Vec<SumNode>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SumProofElement {
+    pub node: SumNode,
+    pub is_right: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SumProof {
+    pub leaf_index: usize,
+    pub leaf_count: usize,
+    pub leaf: SumNode,
+    pub proof_path: Vec<SumProofElement>,
+}
+
+impl MerkleSumTree {
+
+    pub fn new<T: AsRef<str>>(accounts: &[(T, u64)]) -> Result<Self, MerkleError> {
+        let mut levels = Vec::new();
+        let mut current: Vec<SumNode> = accounts
+            .iter()
+            .map(|(id, balance)| SumNode::leaf(id.as_ref(), *balance))
+            .collect();
+
+        while current.len() > 1 {
+            let mut parents = Vec::with_capacity(current.len().div_ceil(2));
+
+            for (index, pair) in current.chunks(2).enumerate() {
+                match pair {
+                    [left, right] => parents.push(SumNode::parent(left, right).ok_or(MerkleError::SumOverflow {
+                        level: levels.len() + 1,
+                        index,
+                    })?),
+                    _ => parents.push(pair[0].clone()),
+                }
+            }
+
+            levels.push(current);
+            current = parents;
+        }
+
+        if !current.is_empty() {
+            levels.push(current);
+        }
+
+        Ok(MerkleSumTree { levels })
+    }
+
+    pub fn root(&self) -> Option<&SumNode> {
+        self.levels.last().and_then(|level| level.first())
+    }
+
+    pub fn total(&self) -> u64 {
+        self.root().map_or(0, |root| root.sum)
+    }
+
+    pub fn leaf_count(&self) -> usize {
+        self.levels.first().map_or(0, Vec::len)
+    }
+
+    pub fn get_proof(&self, leaf_index: usize) -> Result<SumProof, MerkleError> {
+        if leaf_index >= self.leaf_count() {
+            return Err(MerkleError::IndexOutOfRange {
+                index: leaf_index,
+                leaf_count: self.leaf_count(),
+            });
+        }
+
+        let mut proof_path = Vec::new();
+        let mut current_index = leaf_index;
+
+        for level in &self.levels[..self.levels.len() - 1] {
+            let sibling_index = current_index ^ 1;
+
+            if let Some(sibling) = level.get(sibling_index) {
+                proof_path.push(SumProofElement {
+                    node: sibling.clone(),
+                    is_right: sibling_index > current_index,
+                });
+            }
+
+            current_index /= 2;
+        }
+
+        Ok(SumProof {
+            leaf_index,
+            leaf_count: self.leaf_count(),
+            leaf: self.levels[0][leaf_index].clone(),
+            proof_path,
+        })
+    }
+}
+
+impl SumProof {
+
+    // Checks that the account holds exactly `balance` at `leaf_index`, that
+    // every sum on the way up adds up without overflow and that the result
+    // is the published root, total included.
+    pub fn verify(&self, root: &SumNode, id: &str, balance: u64) -> Result<(), ProofError> {
+        if self.leaf_index >= self.leaf_count {
+            return Err(ProofError::IndexOutOfRange {
+                index: self.leaf_index,
+                leaf_count: self.leaf_count,
+            });
+        }
+
+        let directions: Vec<bool> = self.proof_path.iter().map(|element| element.is_right).collect();
+        if directions != promoted_directions(self.leaf_index, self.leaf_count) {
+            return Err(ProofError::PathMismatch {
+                index: self.leaf_index,
+                leaf_count: self.leaf_count,
+            });
+        }
+
+        // Malformed hashes are reported here so a `None` from
+        // `SumNode::parent` below can only mean an overflow.
+        let hashes = std::iter::once(&root.hash).chain(self.proof_path.iter().map(|element| &element.node.hash));
+        for hash in hashes {
+            if !HashMode::Sha512.is_valid_hash(hash) {
+                return Err(ProofError::InvalidHash(hash.clone()));
+            }
+        }
+
+        let expected = SumNode::leaf(id, balance);
+        if self.leaf != expected {
+            return Err(ProofError::LeafMismatch {
+                expected: expected.hash,
+                actual: self.leaf.hash.clone(),
+            });
+        }
+
+        let mut current = expected;
+
+        for (level, element) in self.proof_path.iter().enumerate() {
+            let parent = if element.is_right {
+                SumNode::parent(&current, &element.node)
+            } else {
+                SumNode::parent(&element.node, &current)
+            };
+
+            current = parent.ok_or(ProofError::SumOverflow { level })?;
+        }
+
+        if current.sum != root.sum {
+            return Err(ProofError::SumMismatch {
+                expected: root.sum,
+                actual: current.sum,
+            });
+        }
+
+        if current.hash != root.hash {
+            return Err(ProofError::RootMismatch {
+                expected: root.hash.clone(),
+                actual: current.hash,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn accounts(count: usize) -> Vec<(String, u64)> {
+        (0..count).map(|i| (format!("customer-{}", i), 100 * i as u64 + 7)).collect()
+    }
+
+    #[test]
+    fn test_every_balance_proves_against_total() {
+        for count in 1..=9 {
+            let accounts = accounts(count);
+            let tree = MerkleSumTree::new(&accounts).unwrap();
+            let root = tree.root().unwrap();
+
+            assert_eq!(tree.total(), accounts.iter().map(|(_, balance)| balance).sum::<u64>());
+
+            for (i, (id, balance)) in accounts.iter().enumerate() {
+                let proof = tree.get_proof(i).unwrap();
+                assert_eq!(proof.verify(root, id, *balance), Ok(()), "Account {} of {} failed", i, count);
+            }
+        }
+
+        assert_eq!(MerkleSumTree::new::<String>(&[]).unwrap().total(), 0);
+    }
+
+    #[test]
+    fn test_wrong_balance_or_total_rejected() {
+        let accounts = accounts(6);
+        let tree = MerkleSumTree::new(&accounts).unwrap();
+        let root = tree.root().unwrap();
+        let proof = tree.get_proof(2).unwrap();
+
+        assert!(matches!(
+            proof.verify(root, "customer-2", 206),
+            Err(ProofError::LeafMismatch { .. })
+        ));
+
+        // Hiding liabilities by shrinking a sibling's sum changes the total.
+        let mut shrunk = proof.clone();
+        shrunk.proof_path[1].node.sum -= 100;
+        assert_eq!(
+            shrunk.verify(root, "customer-2", 207),
+            Err(ProofError::SumMismatch { expected: root.sum, actual: root.sum - 100 })
+        );
+
+        // Publishing a smaller total than the tree commits to is caught too.
+        let understated = SumNode { hash: root.hash.clone(), sum: root.sum - 1 };
+        assert!(matches!(
+            proof.verify(&understated, "customer-2", 207),
+            Err(ProofError::SumMismatch { .. })
+        ));
+
+        let mut relabelled = proof;
+        relabelled.proof_path[0].node.hash = "0".repeat(128);
+        assert!(matches!(
+            relabelled.verify(root, "customer-2", 207),
+            Err(ProofError::RootMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_proof_position_is_checked() {
+        let accounts = accounts(5);
+        let tree = MerkleSumTree::new(&accounts).unwrap();
+        let root = tree.root().unwrap();
+        let proof = tree.get_proof(2).unwrap();
+
+        let mut flipped = proof.clone();
+        flipped.proof_path[0].is_right = !flipped.proof_path[0].is_right;
+        assert_eq!(
+            flipped.verify(root, "customer-2", 207),
+            Err(ProofError::PathMismatch { index: 2, leaf_count: 5 })
+        );
+
+        // The promoted last leaf has a shorter path than its neighbours.
+        let mut relabelled = proof.clone();
+        relabelled.leaf_index = 4;
+        assert_eq!(
+            relabelled.verify(root, "customer-2", 207),
+            Err(ProofError::PathMismatch { index: 4, leaf_count: 5 })
+        );
+
+        let mut outside = proof;
+        outside.leaf_index = 5;
+        assert_eq!(
+            outside.verify(root, "customer-2", 207),
+            Err(ProofError::IndexOutOfRange { index: 5, leaf_count: 5 })
+        );
+    }
+
+    #[test]
+    fn test_malformed_hashes_rejected() {
+        let tree = MerkleSumTree::new(&accounts(4)).unwrap();
+        let root = tree.root().unwrap();
+        let proof = tree.get_proof(1).unwrap();
+
+        // Upper-case hex names the same bytes but is not the encoding hashed.
+        let mut shouting = proof.clone();
+        shouting.proof_path[0].node.hash = shouting.proof_path[0].node.hash.to_uppercase();
+        assert!(matches!(
+            shouting.verify(root, "customer-1", 107),
+            Err(ProofError::InvalidHash(_))
+        ));
+
+        let mut short = proof.clone();
+        short.proof_path[1].node.hash.truncate(64);
+        assert!(matches!(short.verify(root, "customer-1", 107), Err(ProofError::InvalidHash(_))));
+
+        let truncated = SumNode { hash: root.hash[..64].to_string(), sum: root.sum };
+        assert!(matches!(proof.verify(&truncated, "customer-1", 107), Err(ProofError::InvalidHash(_))));
+
+        assert_eq!(SumNode::parent(&short.proof_path[1].node, &proof.proof_path[0].node), None);
+    }
+
+    #[test]
+    fn test_overflow_is_checked() {
+        assert_eq!(
+            MerkleSumTree::new(&[("a", u64::MAX), ("b", 1)]),
+            Err(MerkleError::SumOverflow { level: 1, index: 0 })
+        );
+
+        let tree = MerkleSumTree::new(&[("a", 5), ("b", 3)]).unwrap();
+        let mut proof = tree.get_proof(0).unwrap();
+
+        // A sibling sum chosen to wrap the total back to a small number.
+        proof.proof_path[0].node.sum = u64::MAX - 1;
+        assert_eq!(
+            proof.verify(tree.root().unwrap(), "a", 5),
+            Err(ProofError::SumOverflow { level: 0 })
+        );
+    }
+
+    #[test]
+    fn test_negative_sums_cannot_be_encoded() {
+        let tree = MerkleSumTree::new(&accounts(3)).unwrap();
+        let proof = tree.get_proof(1).unwrap();
+
+        let json = serde_json::to_string(&proof).unwrap();
+        let decoded: SumProof = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.verify(tree.root().unwrap(), "customer-1", 107), Ok(()));
+
+        let negative = json.replacen(&format!("\"sum\":{}", proof.proof_path[0].node.sum), "\"sum\":-7", 1);
+        assert_ne!(negative, json);
+        assert!(serde_json::from_str::<SumProof>(&negative).is_err());
+    }
+}
@@ -1,163 +1,585 @@
 use crate::hash::sha512::SHA512;
-use std::fmt::Write;
+use crate::merkle::encoding::MerkleTreeRepr;
+use crate::merkle::error::{MerkleError, ProofError};
+use crate::merkle::mode::HashMode;
+use crate::merkle::node_store::{MemoryStore, NodeStore};
+#[cfg(feature = "parallel")]
+use crate::merkle::parallel;
+use serde::{Deserialize, Serialize};
+use std::io;
+
+pub const DEFAULT_ARITY: usize = 2;
+
+pub(crate) fn default_arity() -> usize {
+    DEFAULT_ARITY
+}
+
+// How the last group of a level is completed when it has fewer than `arity`
+// nodes. `Duplicate` repeats the last node (Bitcoin), `Promote` carries a lone
+// node up unchanged (RFC 6962), and the padding policies fill the group with
+// the hash of an all-padding subtree of the same height, which is the same
+// root as padding the leaves out to a power of the arity.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OddNodePolicy {
+    #[default]
+    Duplicate,
+    Promote,
+    PadZero,
+    PadEmptyLeaf,
+}
+
+impl OddNodePolicy {
+
+    pub(crate) fn padding_leaf(&self, mode: HashMode) -> Option<String> {
+        match self {
+            OddNodePolicy::PadZero => Some(mode.zero_hash()),
+            OddNodePolicy::PadEmptyLeaf => Some(mode.hash_leaf(b"")),
+            OddNodePolicy::Duplicate | OddNodePolicy::Promote => None,
+        }
+    }
+
+    pub(crate) fn next_padding(padding: Option<String>, arity: usize, mode: HashMode) -> Option<String> {
+        padding.map(|pad| mode.hash_children(&vec![pad.as_str(); arity]))
+    }
+}
+
+// The `is_right` flag of every sibling on the path of leaf `index` in a
+// binary tree of `leaf_count` leaves that promotes lone nodes.
+pub(crate) fn promoted_directions(index: usize, leaf_count: usize) -> Vec<bool> {
+    let mut directions = Vec::new();
+    let (mut index, mut len) = (index, leaf_count);
 
-#[derive(Debug, Clone)]
-pub struct MerkleTree {
+    while len > 1 {
+        if index ^ 1 < len {
+            directions.push(index % 2 == 0);
+        }
+        index /= 2;
+        len = len.div_ceil(2);
+    }
+
+    directions
+}
+
+pub(crate) fn short_hash(hash: &str, len: usize) -> &str {
+    hash.get(..len).unwrap_or(hash)
+}
+
+// Deserialized trees go through `MerkleTreeRepr` so a shape the builder
+// would refuse, or a store that does not match it, is rejected up front.
+// The shape is fixed once built, so it is only readable from outside.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(
+    try_from = "MerkleTreeRepr<S>",
+    bound(deserialize = "S: NodeStore + Deserialize<'de>")
+)]
+pub struct MerkleTree<S = MemoryStore> {
     pub root: String,
-    pub levels: Vec<Vec<String>>,
+    #[serde(default = "default_arity")]
+    pub(crate) arity: usize,
+    #[serde(default)]
+    pub(crate) policy: OddNodePolicy,
+    #[serde(default)]
+    pub(crate) mode: HashMode,
+    #[serde(flatten)]
+    pub store: S,
 }
 
 impl MerkleTree {
     
     pub fn new(data: Vec<String>) -> Self {
-        if data.is_empty() {
-            return MerkleTree {
-                root: String::new(),
-                levels: Vec::new(),
-            };
+        Self::from_leaf_hashes(Self::hash_leaves(&data, HashMode::Sha512))
+    }
+
+    pub fn from_leaf_hashes(leaf_hashes: Vec<String>) -> Self {
+        Self::build(MemoryStore::new(), leaf_hashes, DEFAULT_ARITY, OddNodePolicy::Duplicate, HashMode::Sha512)
+            .expect("In-memory node store should not fail")
+    }
+
+    pub(crate) fn hash_leaves(data: &[String], mode: HashMode) -> Vec<String> {
+        #[cfg(feature = "parallel")]
+        if data.len() >= parallel::PARALLEL_THRESHOLD {
+            return parallel::hash_leaves(data, mode);
         }
 
-        let mut current_level: Vec<String> = data
-            .iter()
-            .map(|item| Self::bytes_to_hex(&SHA512::hash(item.as_bytes())))
-            .collect();
+        data.iter()
+            .map(|item| mode.hash_leaf(item.as_bytes()))
+            .collect()
+    }
 
-        let mut levels = vec![current_level.clone()];
+    fn hash_level(
+        level: &[String],
+        arity: usize,
+        policy: OddNodePolicy,
+        padding: Option<&str>,
+        mode: HashMode,
+    ) -> Vec<String> {
+        #[cfg(feature = "parallel")]
+        if level.len() >= parallel::PARALLEL_THRESHOLD {
+            return parallel::hash_level(level, arity, policy, padding, mode);
+        }
 
-        while current_level.len() > 1 {
-            current_level = Self::hash_level(&current_level);
-            levels.push(current_level.clone());
+        let mut parent_level = Vec::new();
+
+        for group in level.chunks(arity) {
+            parent_level.push(Self::hash_group(group, arity, policy, padding, mode));
         }
 
-        let root = current_level.into_iter().next().unwrap_or_default();
+        parent_level 
+    }
 
-        MerkleTree { root, levels }
+    // `padding` is the hash of an all-padding subtree at this level and is
+    // only used by the padding policies.
+    pub(crate) fn hash_group(
+        group: &[String],
+        arity: usize,
+        policy: OddNodePolicy,
+        padding: Option<&str>,
+        mode: HashMode,
+    ) -> String {
+        let last = &group[group.len() - 1];
+
+        let filler = match policy {
+            _ if group.len() == arity => last.as_str(),
+            OddNodePolicy::Duplicate => last.as_str(),
+            // Promote only goes with binary trees, so a short group is a
+            // single node.
+            OddNodePolicy::Promote => return last.clone(),
+            OddNodePolicy::PadZero | OddNodePolicy::PadEmptyLeaf => {
+                padding.expect("Padding policies always have a padding hash")
+            }
+        };
 
+        let children: Vec<&str> = (0..arity)
+            .map(|i| group.get(i).map_or(filler, String::as_str))
+            .collect();
+
+        mode.hash_children(&children)
     }
 
-    fn hash_level(level: &[String]) -> Vec<String> {
-        let mut parent_level = Vec::new();
+    pub(crate) fn hash_children(children: &[&str]) -> String {
+        Self::bytes_to_hex(&SHA512::hash(children.concat().as_bytes()))
+    }
 
-        for i in (0..level.len()).step_by(2) {
-            let left = &level[i];
+    pub(crate) fn hash_leaf(data: &[u8]) -> String {
+        Self::bytes_to_hex(&SHA512::hash(data))
+    }
 
-            let right = level.get(i + 1).unwrap_or(&level[i]);
+    pub(crate) fn hash_pair(left: &str, right: &str) -> String {
+        let combined = format!("{}{}", left, right);
 
-            let combined = format!("{}{}", left, right);
-                
-            let parent_hash = Self::bytes_to_hex(&SHA512::hash(combined.as_bytes()));
+        Self::bytes_to_hex(&SHA512::hash(combined.as_bytes()))
+    }
 
-            parent_level.push(parent_hash);
+    fn bytes_to_hex(bytes: &[u8]) -> String {
+        bytes.iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+}
+
+impl<S: NodeStore> MerkleTree<S> {
+
+    pub fn with_store(store: S, data: Vec<String>) -> io::Result<Self> {
+        let leaf_hashes = MerkleTree::hash_leaves(&data, HashMode::Sha512);
+        Self::build(store, leaf_hashes, DEFAULT_ARITY, OddNodePolicy::Duplicate, HashMode::Sha512)
+    }
+
+    pub fn from_store(store: S) -> io::Result<Self> {
+        Self::from_store_with(store, DEFAULT_ARITY, OddNodePolicy::Duplicate, HashMode::Sha512)
+    }
+
+    pub(crate) fn from_store_with(store: S, arity: usize, policy: OddNodePolicy, mode: HashMode) -> io::Result<Self> {
+        let root = match store.height().checked_sub(1) {
+            Some(top) => store.get(top, 0)?.unwrap_or_default(),
+            None => String::new(),
+        };
+
+        Ok(MerkleTree { root, arity, policy, mode, store })
+    }
+
+    pub(crate) fn build(
+        mut store: S,
+        leaf_hashes: Vec<String>,
+        arity: usize,
+        policy: OddNodePolicy,
+        mode: HashMode,
+    ) -> io::Result<Self> {
+        // Nodes left over from an earlier tree would otherwise linger past
+        // the end of the new levels.
+        store.clear()?;
+
+        if leaf_hashes.is_empty() {
+            return Ok(MerkleTree {
+                root: String::new(),
+                arity,
+                policy,
+                mode,
+                store,
+            });
         }
 
-        parent_level 
+        let mut current_level = leaf_hashes;
+        let mut level_idx = 0;
+        let mut padding = policy.padding_leaf(mode);
+
+        loop {
+            for (i, hash) in current_level.iter().enumerate() {
+                store.put(level_idx, i, hash.clone())?;
+            }
+
+            if current_level.len() == 1 {
+                break;
+            }
+
+            current_level = MerkleTree::hash_level(&current_level, arity, policy, padding.as_deref(), mode);
+            padding = OddNodePolicy::next_padding(padding, arity, mode);
+            level_idx += 1;
+        }
+
+        let root = current_level.into_iter().next().unwrap_or_default();
+
+        Ok(MerkleTree { root, arity, policy, mode, store })
+    }
+
+    pub fn arity(&self) -> usize {
+        self.arity
+    }
+
+    pub fn policy(&self) -> OddNodePolicy {
+        self.policy
+    }
+
+    pub fn mode(&self) -> HashMode {
+        self.mode
     }
 
     pub fn leaf_count(&self) -> usize {
-        self.levels.first().map(|level| level.len().unwrap_or(0))
+        self.store.len(0)
     }
         
     pub fn height(&self) -> usize {
-        self.levels.len()
+        self.store.height()
     }
 
-    pub fn get_proof(&self, leaf_index: usize) -> Option<MerkleProof> {
+    // Multiproofs, range proofs and consistency proofs only support the
+    // default binary SHA512 tree with duplicated odd nodes.
+    pub(crate) fn has_default_shape(&self) -> bool {
+        self.arity == 2 && self.policy == OddNodePolicy::Duplicate && self.mode == HashMode::Sha512
+    }
+
+    pub fn get_proof(&self, leaf_index: usize) -> Result<MerkleProof, MerkleError> {
         if leaf_index >= self.leaf_count() {
-            return None;
+            return Err(MerkleError::IndexOutOfRange {
+                index: leaf_index,
+                leaf_count: self.leaf_count(),
+            });
         }
         let mut proof_path = Vec::new();
         let mut current_index = leaf_index;
+        let mut padding = self.policy.padding_leaf(self.mode);
+
+        for level_idx in 0..self.height() - 1 {
+            let first_sibling = current_index - current_index % self.arity;
+            let level_len = self.store.len(level_idx);
+
+            // A promoted node has no siblings at this level.
+            let promoted = self.policy == OddNodePolicy::Promote && first_sibling + 1 == level_len;
 
-        for level_idx in 0..self.levels.len() - 1 {
-            let level = &self.levels[level_idx];
-                
-            let sibling_index  = if current_index % 2 == 0 {
-                current_index + 1
-            } else {
-                current_index - 1
-            };
+            for sibling_index in first_sibling..first_sibling + self.arity {
+                if promoted || sibling_index == current_index {
+                    continue;
+                }
+
+                // Siblings past the end of a short group are filled in the
+                // same way the tree filled them when it was built.
+                let hash = match &padding {
+                    Some(pad) if sibling_index >= level_len => pad.clone(),
+                    _ => self.node(level_idx, sibling_index.min(level_len - 1))?,
+                };
 
-            if let Some(sibling_hash) = level.get(sibling_index) {
                 proof_path.push(ProofElement {
-                    hash: sibling_hash.clone(),
+                    hash,
                     is_right: sibling_index > current_index,
                 });
             }
 
-            current_index /= 2;
+            padding = OddNodePolicy::next_padding(padding, self.arity, self.mode);
+            current_index /= self.arity;
         }
 
-        Some(MerkleProof {
+        Ok(MerkleProof {
             leaf_index,
-            leaf_hash: self.levels[0][leaf_index].clone(),
+            leaf_hash: self.node(0, leaf_index)?,
+            arity: self.arity,
+            mode: self.mode,
             proof_path,
         })
     }
 
+    pub(crate) fn node(&self, level: usize, index: usize) -> Result<String, MerkleError> {
+        self.store
+            .get(level, index)
+            .map_err(|err| MerkleError::Io(err.into()))?
+            .ok_or(MerkleError::MissingNode { level, index })
+    }
 
-    pub fn verify_proof(&self, proof: &MerkleProof) -> bool {
-        let mut current_hash = proof.leaf_hash.clone();
-            
-        for element in &proof.proof_path {
-            if element.is_right {
-                current_hash = Self::bytes_to_hex(&SHA512::hash(
-                    format!("{}{}", current_hash, element.hash).as_bytes(),
-                ));
-            } else {
+    // Checks the proof against this tree's shape and stored leaf before
+    // recomputing the root, so callers learn which part of the proof is off.
+    pub fn verify_proof(&self, proof: &MerkleProof) -> Result<(), ProofError> {
+        // Promoted nodes skip levels, so only the other policies have a fixed depth.
+        let depth = (self.policy != OddNodePolicy::Promote).then(|| self.height().saturating_sub(1));
 
-                current_hash = Self::bytes_to_hex(&SHA512::hash(
-                    format!("{}{}", element.hash, current_hash).as_bytes(),
-                ));
+        proof.check_shape(self.arity, self.mode, depth)?;
+        let leaf_hash = self
+            .store
+            .get(0, proof.leaf_index)
+            .map_err(|err| ProofError::Io(err.into()))?;
+
+        proof.check_leaf(leaf_hash, self.leaf_count())?;
+        proof.verify(&self.root)
+    }
+
+    pub fn update(&mut self, leaf_index: usize, data: String) -> Result<(), MerkleError> {
+        if leaf_index >= self.leaf_count() {
+            return Err(MerkleError::IndexOutOfRange {
+                index: leaf_index,
+                leaf_count: self.leaf_count(),
+            });
+        }
+
+        let mut current_hash = self.mode.hash_leaf(data.as_bytes());
+        let mut current_index = leaf_index;
+        let mut padding = self.policy.padding_leaf(self.mode);
+        self.put(0, current_index, current_hash.clone())?;
+
+        for level_idx in 0..self.height() - 1 {
+            let first_sibling = current_index - current_index % self.arity;
+            let last_node = self.store.len(level_idx) - 1;
+            let mut group = Vec::with_capacity(self.arity);
+
+            for sibling_index in first_sibling..=(first_sibling + self.arity - 1).min(last_node) {
+                if sibling_index == current_index {
+                    group.push(current_hash.clone());
+                } else {
+                    group.push(self.node(level_idx, sibling_index)?);
+                }
             }
+
+            current_hash = MerkleTree::hash_group(&group, self.arity, self.policy, padding.as_deref(), self.mode);
+            padding = OddNodePolicy::next_padding(padding, self.arity, self.mode);
+            current_index /= self.arity;
+            self.put(level_idx + 1, current_index, current_hash.clone())?;
         }
-        current_hash == self.root 
+
+        self.root = current_hash;
+        Ok(())
     }
 
-    fn bytes_to_hex(bytes: &[u8]) -> string {
-        bytes.iter()
-            .map(|b| format!("{:02x", b))
-            .collect()
+    fn put(&mut self, level: usize, index: usize, hash: String) -> Result<(), MerkleError> {
+        self.store
+            .put(level, index, hash)
+            .map_err(|err| MerkleError::Io(err.into()))
     }
+}
 
-    pub fn display(&self) {
-        println!("\n{}", "=".repeat(60));
-        println!(" MERKLE TREE");
-        println!("{}", "=".repeat(60));
-        println!("Root: {}", &self.root[..32]);
-        println!("Height: {}\n", self.height());
+impl<S: NodeStore> std::fmt::Display for MerkleTree<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}", "=".repeat(60))?;
+        writeln!(f, " MERKLE TREE")?;
+        writeln!(f, "{}", "=".repeat(60))?;
+        writeln!(f, "Root: {}", short_hash(&self.root, 32))?;
+        writeln!(f, "Height: {}", self.height())?;
 
-        for (level_idx, level) in self.levels.iter().enumerate() {
+        for level_idx in 0..self.height() {
             let indent = "  ".repeat(level_idx);
-            println!("{}Level {}:", indent, level_idx);
-                
-            for (i, hash) in level.iter().enumerate() {
-                println!("{} [{}] {}", indent, i, &hash[..24]);
+            writeln!(f, "\n{}Level {}:", indent, level_idx)?;
+
+            for i in 0..self.store.len(level_idx) {
+                let hash = self.node(level_idx, i).unwrap_or_default();
+                writeln!(f, "{} [{}] {}", indent, i, short_hash(&hash, 24))?;
             }
-            println!();
         }
+        Ok(())
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ProofElement {
     pub hash: String,
     pub is_right: bool,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct MerkleProof {
     pub leaf_index: usize,
     pub leaf_hash: String,
+    #[serde(default = "default_arity")]
+    pub arity: usize,
+    #[serde(default)]
+    pub mode: HashMode,
     pub proof_path: Vec<ProofElement>,
 }
 
+impl MerkleProof {
+
+    pub fn depth(&self) -> usize {
+        self.proof_path.len().div_ceil(self.arity.max(2) - 1)
+    }
+
+    pub(crate) fn check_shape(&self, arity: usize, mode: HashMode, depth: Option<usize>) -> Result<(), ProofError> {
+        if self.arity != arity {
+            return Err(ProofError::ArityMismatch {
+                expected: arity,
+                actual: self.arity,
+            });
+        }
+
+        if self.mode != mode {
+            return Err(ProofError::HashModeMismatch {
+                expected: mode,
+                actual: self.mode,
+            });
+        }
+
+        match depth {
+            Some(expected) if self.depth() != expected => Err(ProofError::DepthMismatch {
+                expected,
+                actual: self.depth(),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    // `leaf_hash` is the tree's own leaf at the proof's index, if it has one.
+    pub(crate) fn check_leaf(&self, leaf_hash: Option<String>, leaf_count: usize) -> Result<(), ProofError> {
+        let expected = leaf_hash.ok_or(ProofError::IndexOutOfRange {
+            index: self.leaf_index,
+            leaf_count,
+        })?;
+
+        if self.leaf_hash != expected {
+            return Err(ProofError::LeafMismatch {
+                expected,
+                actual: self.leaf_hash.clone(),
+            });
+        }
+
+        Ok(())
+    }
+
+    // Under `Promote` the siblings a leaf needs follow from its index and the
+    // leaf count alone, so a path that skips or adds levels is rejected
+    // before anything is hashed.
+    pub(crate) fn check_promoted_path(&self, leaf_count: usize) -> Result<(), ProofError> {
+        if self.leaf_index >= leaf_count {
+            return Err(ProofError::IndexOutOfRange {
+                index: self.leaf_index,
+                leaf_count,
+            });
+        }
+
+        let directions: Vec<bool> = self.proof_path.iter().map(|element| element.is_right).collect();
+        if directions != promoted_directions(self.leaf_index, leaf_count) {
+            return Err(ProofError::PathMismatch {
+                index: self.leaf_index,
+                leaf_count,
+            });
+        }
+
+        Ok(())
+    }
+
+    // Under `Duplicate` a leaf of a binary tree has one sibling per level,
+    // on its right exactly when its index bit at that level is clear, so the
+    // index and leaf count alone fix the path. A proof for an inner node
+    // passed off as a leaf is one level short and is rejected here.
+    pub(crate) fn check_duplicated_path(&self, leaf_count: usize) -> Result<(), ProofError> {
+        if self.leaf_index >= leaf_count {
+            return Err(ProofError::IndexOutOfRange {
+                index: self.leaf_index,
+                leaf_count,
+            });
+        }
+
+        let mut depth = 0;
+        let mut len = leaf_count;
+        while len > 1 {
+            len = len.div_ceil(2);
+            depth += 1;
+        }
+
+        if self.proof_path.len() != depth {
+            return Err(ProofError::DepthMismatch {
+                expected: depth,
+                actual: self.proof_path.len(),
+            });
+        }
+
+        let directions = self.proof_path.iter().map(|element| element.is_right);
+        if !directions.enumerate().all(|(level, is_right)| is_right == ((self.leaf_index >> level) & 1 == 0)) {
+            return Err(ProofError::PathMismatch {
+                index: self.leaf_index,
+                leaf_count,
+            });
+        }
+
+        Ok(())
+    }
+
+    pub fn verify(&self, root: &str) -> Result<(), ProofError> {
+        let computed = self.compute_root()?;
+
+        if computed != root {
+            return Err(ProofError::RootMismatch {
+                expected: root.to_string(),
+                actual: computed,
+            });
+        }
+
+        Ok(())
+    }
+
+    // Each level contributes `arity - 1` siblings; the node's position in its
+    // group is the number of siblings to its left. Every hash is checked
+    // against the proof's mode first, since the Bitcoin and RFC 6962 hashing
+    // code only takes well-formed hashes.
+    pub fn compute_root(&self) -> Result<String, ProofError> {
+        let hashes = std::iter::once(&self.leaf_hash).chain(self.proof_path.iter().map(|element| &element.hash));
+        for hash in hashes {
+            if !self.mode.is_valid_hash(hash) {
+                return Err(ProofError::InvalidHash(hash.clone()));
+            }
+        }
+
+        let mut current_hash = self.leaf_hash.clone();
+
+        for level in self.proof_path.chunks(self.arity.max(2) - 1) {
+            let mut children: Vec<&str> = level
+                .iter()
+                .filter(|element| !element.is_right)
+                .map(|element| element.hash.as_str())
+                .collect();
+            children.push(&current_hash);
+            children.extend(
+                level
+                    .iter()
+                    .filter(|element| element.is_right)
+                    .map(|element| element.hash.as_str()),
+            );
+
+            current_hash = self.mode.hash_children(&children);
+        }
+        Ok(current_hash)
+    }
+}
+
 impl std::fmt::Display for MerkleProof {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
             "MerkleProof(\n Leaf Index: {} \n Leaf Hash: {}...\n Proof Elements: {}\n)",
             self.leaf_index,
-            &self.leaf_hash[..24]
+            short_hash(&self.leaf_hash, 24),
             self.proof_path.len()
         )
     }
@@ -166,6 +588,7 @@ impl std::fmt::Display for MerkleProof {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::merkle::builder::MerkleTreeBuilder;
     
     #[test]
     fn test_merkle_tree_creation() {
@@ -178,11 +601,20 @@ mod tests {
         
         let tree = MerkleTree::new(data);
 
-        assert_eq!(tree.leaf_count(), 3);
+        assert_eq!(tree.leaf_count(), 4);
         assert_eq!(tree.height(), 3);
         assert!(!tree.root.is_empty());
     }
 
+    #[test]
+    fn test_merkle_tree_display() {
+        let tree = MerkleTree::new(vec!["A".to_string(), "B".to_string(), "C".to_string()]);
+        let text = tree.to_string();
+
+        assert!(text.contains(short_hash(&tree.root, 32)));
+        assert_eq!(text.matches(" [").count(), 6);
+    }
+
     #[test]
     fn test_merkle_proof_generation() {
         let data = vec![
@@ -210,11 +642,11 @@ mod tests {
             "Tx D".to_string(),
         ];
         
-        let tree = Merkle::new(data);
+        let tree = MerkleTree::new(data);
         
         for i in 0..tree.leaf_count() {
             let proof = tree.get_proof(i).expect("Should generate proof");
-            assert!(tree.verify_proof(&proof), "Proof for leaf {} should be valid", i);
+            assert!(tree.verify_proof(&proof).is_ok(), "Proof for leaf {} should be valid", i);
         }
     }
     
@@ -235,11 +667,11 @@ mod tests {
             proof.proof_path[0].hash = "0".repeat(128);
         }
 
-        assert!(!tree.verify_proof(&proof), "Tampered proof should fail verification");
+        assert!(tree.verify_proof(&proof).is_err(), "Tampered proof should fail verification");
     }
 
     #[test]
-    fn test_invalid_proof_detection() {
+    fn test_proof_for_different_leaves() {
         let data = vec![
             "Tx A".to_string(),
             "Tx B".to_string(),
@@ -248,29 +680,106 @@ mod tests {
         ];
 
         let tree = MerkleTree::new(data);
-        let mut proof = tree.get_proof(0).expect("Should generate proof");
-
-        if !proof.groof_path.is_empty() {
-            proof.proof_path[0].hash = "0".repeat(128);
-        }
+        let proof0 = tree.get_proof(0).expect("Should generate proof");
+        let proof3 = tree.get_proof(3).expect("Should generate proof");
         
-        assert!(!tree.verify_proof(&proof),"Tampered proof should fail verification");
+        assert_ne!(proof0.leaf_index, proof3.leaf_index);
+        assert_ne!(proof0.leaf_hash, proof3.leaf_hash);
     }
 
     #[test]
-    fn test_proof_for_different_leaves() {
+    fn test_proof_verification_with_odd_leaf_count() {
         let data = vec![
             "Tx A".to_string(),
             "Tx B".to_string(),
             "Tx C".to_string(),
             "Tx D".to_string(),
+            "Tx E".to_string(),
         ];
 
         let tree = MerkleTree::new(data);
-        let proof0 = tree.get_proof(0).expect("Should generate proof");
-        let proof3 = tree.get_proof(0).expect("Should generate proof");
-        
-        assert_ne!(proof0.leaf_index, proof3.leaf_index);
-        assert_ne!(proof0.leaf_hash, proof3.leaf_hash);
+
+        for i in 0..tree.leaf_count() {
+            let proof = tree.get_proof(i).expect("Should generate proof");
+            assert_eq!(proof.proof_path.len(), tree.height() - 1);
+            assert!(tree.verify_proof(&proof).is_ok(), "Proof for leaf {} should be valid", i);
+        }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_get_proof_errors() {
+        let mut tree = MerkleTree::new(vec!["Tx A".to_string(), "Tx B".to_string()]);
+
+        assert_eq!(
+            tree.get_proof(2),
+            Err(MerkleError::IndexOutOfRange { index: 2, leaf_count: 2 })
+        );
+        assert_eq!(
+            tree.update(2, "Tx C".to_string()),
+            Err(MerkleError::IndexOutOfRange { index: 2, leaf_count: 2 })
+        );
+        assert_eq!(
+            MerkleTree::new(Vec::new()).get_proof(0),
+            Err(MerkleError::IndexOutOfRange { index: 0, leaf_count: 0 })
+        );
+    }
+
+    #[test]
+    fn test_verify_proof_reports_reason() {
+        let data: Vec<String> = (0..5).map(|i| format!("Tx {}", i)).collect();
+        let tree = MerkleTree::new(data);
+        let proof = tree.get_proof(3).unwrap();
+
+        let mut tampered = proof.clone();
+        tampered.proof_path[1].hash = "0".repeat(128);
+        match tree.verify_proof(&tampered) {
+            Err(ProofError::RootMismatch { expected, actual }) => {
+                assert_eq!(expected, tree.root);
+                assert_eq!(Ok(actual), tampered.compute_root());
+            }
+            other => panic!("Expected a root mismatch, got {:?}", other),
+        }
+
+        let mut moved = proof.clone();
+        moved.leaf_index = 4;
+        assert_eq!(
+            tree.verify_proof(&moved),
+            Err(ProofError::LeafMismatch {
+                expected: tree.store.get(0, 4).unwrap().unwrap(),
+                actual: proof.leaf_hash.clone(),
+            })
+        );
+
+        let mut missing = proof.clone();
+        missing.leaf_index = 5;
+        assert_eq!(
+            tree.verify_proof(&missing),
+            Err(ProofError::IndexOutOfRange { index: 5, leaf_count: 5 })
+        );
+
+        let mut short = proof.clone();
+        short.proof_path.pop();
+        assert_eq!(tree.verify_proof(&short), Err(ProofError::DepthMismatch { expected: 3, actual: 2 }));
+
+        let mut wrong_mode = proof;
+        wrong_mode.mode = HashMode::Bitcoin;
+        assert!(matches!(tree.verify_proof(&wrong_mode), Err(ProofError::HashModeMismatch { .. })));
+    }
+
+    #[test]
+    fn test_malformed_proof_hashes_rejected() {
+        for mode in [HashMode::Sha512, HashMode::Bitcoin, HashMode::Rfc6962] {
+            let leaves = (0..5).map(|i| mode.hash_leaf(format!("Tx {}", i).as_bytes())).collect();
+            let tree = MerkleTreeBuilder::new().hash_mode(mode).build_from_leaf_hashes(leaves);
+            let proof = tree.get_proof(2).unwrap();
+            assert_eq!(proof.compute_root(), Ok(tree.root.clone()));
+
+            for bad in ["zz".repeat(mode.hash_len()), "ab".to_string(), "AB".repeat(mode.hash_len())] {
+                let mut malformed = proof.clone();
+                malformed.proof_path[1].hash = bad.clone();
+                assert_eq!(malformed.compute_root(), Err(ProofError::InvalidHash(bad.clone())));
+                assert_eq!(malformed.verify(&tree.root), Err(ProofError::InvalidHash(bad)));
+            }
+        }
+    }
+}
@@ -0,0 +1,52 @@
+use crate::merkle::error::{IoError, ProofError};
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignatureError {
+    KeyReused,
+    LengthMismatch { expected: usize, actual: usize },
+    InvalidHash(String),
+    InvalidPreimage { bit: usize },
+    KeysExhausted { capacity: u64 },
+    InvalidHeight { height: u32, max: u32 },
+    IndexMismatch { index: usize },
+    InvalidAuthPath(ProofError),
+    Io(IoError),
+}
+
+impl fmt::Display for SignatureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignatureError::KeyReused => write!(f, "one-time signing key has already been used"),
+            SignatureError::LengthMismatch { expected, actual } => {
+                write!(f, "expected {} hashes, got {}", expected, actual)
+            }
+            SignatureError::InvalidHash(hash) => write!(f, "invalid hex hash: {}", hash),
+            SignatureError::InvalidPreimage { bit } => {
+                write!(f, "revealed preimage for digest bit {} does not match the public key", bit)
+            }
+            SignatureError::KeysExhausted { capacity } => {
+                write!(f, "all {} one-time keys have been used", capacity)
+            }
+            SignatureError::InvalidHeight { height, max } => {
+                write!(f, "tree height {} exceeds the maximum of {}", height, max)
+            }
+            SignatureError::IndexMismatch { index } => {
+                write!(f, "authentication path does not lead to leaf {}", index)
+            }
+            SignatureError::InvalidAuthPath(err) => write!(f, "invalid authentication path: {}", err),
+            SignatureError::Io(err) => write!(f, "failed to persist signing state: {}", err),
+        }
+    }
+}
+
+impl Error for SignatureError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SignatureError::InvalidAuthPath(err) => Some(err),
+            SignatureError::Io(err) => Some(err.get_ref()),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,224 @@
+use crate::hash::sha512::SHA512;
+use crate::sig::error::SignatureError;
+use serde::{Deserialize, Serialize};
+
+pub const DIGEST_BITS: usize = 512;
+
+// Digest bit `i`, most significant bit of the first byte first.
+pub(crate) fn digest_bit(digest: &[u8; 64], i: usize) -> usize {
+    ((digest[i / 8] >> (7 - i % 8)) & 1) as usize
+}
+
+// Every hash and preimage is exactly 64 bytes in lowercase hex, so each has
+// a single encoding and neighbouring values cannot trade bytes.
+pub(crate) fn decode_hash(hash: &str) -> Result<[u8; 64], SignatureError> {
+    hex::decode(hash)
+        .ok()
+        .filter(|bytes| hex::encode(bytes) == hash)
+        .and_then(|bytes| <[u8; 64]>::try_from(bytes).ok())
+        .ok_or_else(|| SignatureError::InvalidHash(hash.to_string()))
+}
+
+// The secret preimage for `value` at digest bit `bit` is
+// SHA512(seed || bit (u16 BE) || value), so the whole key follows from the seed.
+fn preimage(seed: &[u8], bit: usize, value: usize) -> [u8; 64] {
+    let mut input = Vec::with_capacity(seed.len() + 3);
+    input.extend_from_slice(seed);
+    input.extend_from_slice(&(bit as u16).to_be_bytes());
+    input.push(value as u8);
+    SHA512::hash(&input)
+}
+
+// Only the seed is kept. The key is not `Clone` so a copy cannot be used to
+// sign a second message by accident.
+#[derive(Serialize, Deserialize)]
+pub struct SigningKey {
+    seed: Vec<u8>,
+    used: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PublicKey {
+    pub hashes: Vec<[String; 2]>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Signature {
+    pub preimages: Vec<String>,
+}
+
+impl SigningKey {
+
+    pub fn from_seed(seed: &[u8]) -> Self {
+        SigningKey {
+            seed: seed.to_vec(),
+            used: false,
+        }
+    }
+
+    pub fn is_used(&self) -> bool {
+        self.used
+    }
+
+    pub fn public_key(&self) -> PublicKey {
+        let hashes = (0..DIGEST_BITS)
+            .map(|bit| [0, 1].map(|value| hex::encode(SHA512::hash(&preimage(&self.seed, bit, value)))))
+            .collect();
+
+        PublicKey { hashes }
+    }
+
+    // Signs the SHA512 digest of `message`, revealing one preimage per bit.
+    pub fn sign(&mut self, message: &[u8]) -> Result<Signature, SignatureError> {
+        if self.used {
+            return Err(SignatureError::KeyReused);
+        }
+        self.used = true;
+
+        let digest = SHA512::hash(message);
+        let preimages = (0..DIGEST_BITS)
+            .map(|bit| hex::encode(preimage(&self.seed, bit, digest_bit(&digest, bit))))
+            .collect();
+
+        Ok(Signature { preimages })
+    }
+}
+
+impl PublicKey {
+
+    // A single hash committing to the whole key, used as a leaf when many
+    // one-time keys are collected under a Merkle root.
+    pub fn fingerprint(&self) -> Result<String, SignatureError> {
+        let mut bytes = Vec::with_capacity(DIGEST_BITS * 2 * 64);
+
+        for pair in &self.hashes {
+            for hash in pair {
+                bytes.extend(decode_hash(hash)?);
+            }
+        }
+
+        Ok(hex::encode(SHA512::hash(&bytes)))
+    }
+
+    pub fn verify(&self, message: &[u8], signature: &Signature) -> Result<(), SignatureError> {
+        for len in [self.hashes.len(), signature.preimages.len()] {
+            if len != DIGEST_BITS {
+                return Err(SignatureError::LengthMismatch {
+                    expected: DIGEST_BITS,
+                    actual: len,
+                });
+            }
+        }
+
+        let digest = SHA512::hash(message);
+
+        for (bit, revealed) in signature.preimages.iter().enumerate() {
+            let bytes = decode_hash(revealed)?;
+
+            if hex::encode(SHA512::hash(&bytes)) != self.hashes[bit][digest_bit(&digest, bit)] {
+                return Err(SignatureError::InvalidPreimage { bit });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign_and_verify() {
+        let mut key = SigningKey::from_seed(b"lamport test seed");
+        let public = key.public_key();
+        let signature = key.sign(b"Transfer 10 coins to Bob").unwrap();
+
+        assert_eq!(public.hashes.len(), DIGEST_BITS);
+        assert_eq!(signature.preimages.len(), DIGEST_BITS);
+        assert_eq!(public.verify(b"Transfer 10 coins to Bob", &signature), Ok(()));
+
+        // Keys are a pure function of the seed.
+        assert_eq!(SigningKey::from_seed(b"lamport test seed").public_key(), public);
+        assert_ne!(SigningKey::from_seed(b"another seed").public_key(), public);
+    }
+
+    #[test]
+    fn test_key_refuses_second_signature() {
+        let mut key = SigningKey::from_seed(b"one time");
+        assert!(!key.is_used());
+
+        key.sign(b"first").unwrap();
+        assert!(key.is_used());
+        assert!(matches!(key.sign(b"second"), Err(SignatureError::KeyReused)));
+
+        // The used flag survives a round trip through storage.
+        let json = serde_json::to_string(&key).unwrap();
+        let mut restored: SigningKey = serde_json::from_str(&json).unwrap();
+        assert!(matches!(restored.sign(b"second"), Err(SignatureError::KeyReused)));
+    }
+
+    #[test]
+    fn test_forged_signatures_rejected() {
+        let mut key = SigningKey::from_seed(b"forgery");
+        let public = key.public_key();
+        let signature = key.sign(b"original").unwrap();
+
+        assert!(matches!(
+            public.verify(b"modified", &signature),
+            Err(SignatureError::InvalidPreimage { .. })
+        ));
+
+        let mut tampered = signature.clone();
+        tampered.preimages[100] = hex::encode([0u8; 64]);
+        assert_eq!(
+            public.verify(b"original", &tampered),
+            Err(SignatureError::InvalidPreimage { bit: 100 })
+        );
+
+        let mut truncated = signature.clone();
+        truncated.preimages.pop();
+        assert_eq!(
+            public.verify(b"original", &truncated),
+            Err(SignatureError::LengthMismatch { expected: DIGEST_BITS, actual: DIGEST_BITS - 1 })
+        );
+
+        let mut malformed = signature.clone();
+        malformed.preimages[0] = "not hex".to_string();
+        assert_eq!(
+            public.verify(b"original", &malformed),
+            Err(SignatureError::InvalidHash("not hex".to_string()))
+        );
+
+        // Re-encoding a valid preimage is not a second valid signature.
+        let mut uppercase = signature;
+        uppercase.preimages[0] = uppercase.preimages[0].to_uppercase();
+        assert!(matches!(public.verify(b"original", &uppercase), Err(SignatureError::InvalidHash(_))));
+    }
+
+    #[test]
+    fn test_serde_roundtrip() {
+        let mut key = SigningKey::from_seed(b"serde");
+        let public = key.public_key();
+        let signature = key.sign(b"message").unwrap();
+
+        let public: PublicKey = serde_json::from_str(&serde_json::to_string(&public).unwrap()).unwrap();
+        let signature: Signature = serde_json::from_str(&serde_json::to_string(&signature).unwrap()).unwrap();
+
+        assert_eq!(public.verify(b"message", &signature), Ok(()));
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        let public = SigningKey::from_seed(b"fingerprint").public_key();
+
+        let fingerprint = public.fingerprint().unwrap();
+
+        assert_eq!(fingerprint.len(), 128);
+        assert_ne!(fingerprint, SigningKey::from_seed(b"other").public_key().fingerprint().unwrap());
+
+        let mut short = public;
+        short.hashes[3][1].truncate(126);
+        assert_eq!(short.fingerprint(), Err(SignatureError::InvalidHash(short.hashes[3][1].clone())));
+    }
+}
@@ -0,0 +1,6 @@
+pub mod error;
+pub mod lamport;
+pub mod mss;
+
+pub use error::SignatureError;
+pub use mss::{MssPublicKey, MssSignature, MssSigningKey};
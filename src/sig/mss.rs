@@ -0,0 +1,429 @@
+use crate::hash::sha512::SHA512;
+use crate::merkle::error::ProofError;
+use crate::merkle::mode::HashMode;
+use crate::merkle::tree::{MerkleProof, MerkleTree, DEFAULT_ARITY};
+use crate::sig::error::SignatureError;
+use crate::sig::lamport::{self, decode_hash, digest_bit, DIGEST_BITS};
+use serde::{Deserialize, Serialize};
+use std::cell::OnceCell;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+pub const MAX_HEIGHT: u32 = 20;
+
+// Appending to the whole file name keeps the temporary file distinct from
+// `path` whatever its extension.
+fn temp_path(path: &Path) -> PathBuf {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    PathBuf::from(tmp)
+}
+
+// The state holds the secret seed, so the file is only readable by its
+// owner. A stale temporary file is removed first because opening an existing
+// file keeps its old permissions.
+#[cfg(unix)]
+fn create_private(path: &Path) -> io::Result<File> {
+    use std::os::unix::fs::OpenOptionsExt;
+
+    remove_stale(path)?;
+    OpenOptions::new().write(true).create_new(true).mode(0o600).open(path)
+}
+
+#[cfg(not(unix))]
+fn create_private(path: &Path) -> io::Result<File> {
+    remove_stale(path)?;
+    OpenOptions::new().write(true).create_new(true).open(path)
+}
+
+fn remove_stale(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(unix)]
+fn sync_parent(path: &Path) -> io::Result<()> {
+    let parent = path.parent().filter(|parent| !parent.as_os_str().is_empty());
+    File::open(parent.unwrap_or(Path::new(".")))?.sync_all()
+}
+
+#[cfg(not(unix))]
+fn sync_parent(_path: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+// One-time key `index` is a Lamport key seeded with SHA512(seed || index (u64 BE)).
+fn ots_key(seed: &[u8], index: u64) -> lamport::SigningKey {
+    let mut input = seed.to_vec();
+    input.extend_from_slice(&index.to_be_bytes());
+    lamport::SigningKey::from_seed(&SHA512::hash(&input))
+}
+
+// Only the seed and the next unused index are persisted. The tree over the
+// one-time key fingerprints is rebuilt on first use after loading.
+#[derive(Serialize, Deserialize)]
+pub struct MssSigningKey {
+    seed: Vec<u8>,
+    height: u32,
+    next_index: u64,
+    #[serde(skip)]
+    tree: OnceCell<MerkleTree>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MssPublicKey {
+    pub root: String,
+    pub height: u32,
+}
+
+// `complement` holds, for every digest bit, the Lamport public key hash that
+// the one-time signature does not reveal, so the verifier can rebuild the
+// whole one-time public key and find it under the root.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MssSignature {
+    pub ots: lamport::Signature,
+    pub complement: Vec<String>,
+    pub auth_path: MerkleProof,
+}
+
+impl MssSigningKey {
+
+    pub fn generate(seed: &[u8], height: u32) -> Result<Self, SignatureError> {
+        if height > MAX_HEIGHT {
+            return Err(SignatureError::InvalidHeight { height, max: MAX_HEIGHT });
+        }
+
+        Ok(MssSigningKey {
+            seed: seed.to_vec(),
+            height,
+            next_index: 0,
+            tree: OnceCell::new(),
+        })
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let key: MssSigningKey = serde_json::from_slice(&fs::read(path)?)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+
+        if key.height > MAX_HEIGHT || key.next_index > key.capacity() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "invalid signing key state"));
+        }
+
+        Ok(key)
+    }
+
+    // Writes a temporary file next to `path` and renames it over `path`, so
+    // the file always holds either the previous or the new state. The
+    // directory is synced too, otherwise the rename itself can be lost.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let path = path.as_ref();
+        let tmp = temp_path(path);
+        let bytes = serde_json::to_vec(self).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+
+        let mut file = create_private(&tmp)?;
+        file.write_all(&bytes)?;
+        file.sync_all()?;
+
+        fs::rename(&tmp, path)?;
+        sync_parent(path)
+    }
+
+    pub fn capacity(&self) -> u64 {
+        1 << self.height
+    }
+
+    pub fn next_index(&self) -> u64 {
+        self.next_index
+    }
+
+    pub fn remaining(&self) -> u64 {
+        self.capacity() - self.next_index
+    }
+
+    pub fn public_key(&self) -> MssPublicKey {
+        MssPublicKey {
+            root: self.tree().root.clone(),
+            height: self.height,
+        }
+    }
+
+    fn tree(&self) -> &MerkleTree {
+        self.tree.get_or_init(|| {
+            let leaves = (0..self.capacity())
+                .map(|index| {
+                    ots_key(&self.seed, index)
+                        .public_key()
+                        .fingerprint()
+                        .expect("Generated keys are well formed")
+                })
+                .collect();
+
+            MerkleTree::from_leaf_hashes(leaves)
+        })
+    }
+
+    // The only way to sign: the advanced index reaches disk before the
+    // signature exists, so a crash can waste a one-time key but never reuse one.
+    pub fn sign_persisted<P: AsRef<Path>>(&mut self, path: P, message: &[u8]) -> Result<MssSignature, SignatureError> {
+        let index = self.reserve()?;
+        self.save(path).map_err(|err| SignatureError::Io(err.into()))?;
+        self.sign_at(index, message)
+    }
+
+    fn reserve(&mut self) -> Result<u64, SignatureError> {
+        if self.next_index >= self.capacity() {
+            return Err(SignatureError::KeysExhausted {
+                capacity: self.capacity(),
+            });
+        }
+
+        self.next_index += 1;
+        Ok(self.next_index - 1)
+    }
+
+    fn sign_at(&self, index: u64, message: &[u8]) -> Result<MssSignature, SignatureError> {
+        let mut ots_key = ots_key(&self.seed, index);
+        let ots_public = ots_key.public_key();
+        let ots = ots_key.sign(message)?;
+
+        let digest = SHA512::hash(message);
+        let complement = ots_public
+            .hashes
+            .iter()
+            .enumerate()
+            .map(|(bit, pair)| pair[1 - digest_bit(&digest, bit)].clone())
+            .collect();
+
+        Ok(MssSignature {
+            ots,
+            complement,
+            auth_path: self.tree().get_proof(index as usize).expect("Index is within the tree"),
+        })
+    }
+}
+
+impl MssPublicKey {
+
+    pub fn verify(&self, message: &[u8], signature: &MssSignature) -> Result<(), SignatureError> {
+        let proof = &signature.auth_path;
+        let capacity = 1usize << self.height.min(MAX_HEIGHT);
+
+        proof
+            .check_shape(DEFAULT_ARITY, HashMode::Sha512, Some(self.height as usize))
+            .map_err(SignatureError::InvalidAuthPath)?;
+
+        if proof.leaf_index >= capacity {
+            return Err(SignatureError::InvalidAuthPath(ProofError::IndexOutOfRange {
+                index: proof.leaf_index,
+                leaf_count: capacity,
+            }));
+        }
+
+        // A sibling on the right at level `i` means bit `i` of the index is 0.
+        let directions_match = proof
+            .proof_path
+            .iter()
+            .enumerate()
+            .all(|(level, element)| element.is_right == ((proof.leaf_index >> level) & 1 == 0));
+
+        if !directions_match {
+            return Err(SignatureError::IndexMismatch { index: proof.leaf_index });
+        }
+
+        for len in [signature.ots.preimages.len(), signature.complement.len()] {
+            if len != DIGEST_BITS {
+                return Err(SignatureError::LengthMismatch {
+                    expected: DIGEST_BITS,
+                    actual: len,
+                });
+            }
+        }
+
+        let digest = SHA512::hash(message);
+        let mut hashes = Vec::with_capacity(DIGEST_BITS);
+
+        for (bit, (revealed, other)) in signature.ots.preimages.iter().zip(&signature.complement).enumerate() {
+            let bytes = decode_hash(revealed)?;
+
+            let mut pair = [other.clone(), other.clone()];
+            pair[digest_bit(&digest, bit)] = hex::encode(SHA512::hash(&bytes));
+            hashes.push(pair);
+        }
+
+        let ots_public = lamport::PublicKey { hashes };
+
+        proof
+            .check_leaf(Some(ots_public.fingerprint()?), capacity)
+            .and_then(|_| proof.verify(&self.root))
+            .map_err(SignatureError::InvalidAuthPath)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("mss-{}-{}.json", std::process::id(), name))
+    }
+
+    // In-memory signing for tests only; an index advanced without a save is
+    // reused if the process dies.
+    impl MssSigningKey {
+        fn sign(&mut self, message: &[u8]) -> Result<MssSignature, SignatureError> {
+            let index = self.reserve()?;
+            self.sign_at(index, message)
+        }
+    }
+
+    #[test]
+    fn test_signs_with_every_key_once() {
+        let mut key = MssSigningKey::generate(b"release signing seed", 2).unwrap();
+        let public = key.public_key();
+
+        for i in 0..4 {
+            let message = format!("manifest v{}", i);
+            let signature = key.sign(message.as_bytes()).unwrap();
+
+            assert_eq!(signature.auth_path.leaf_index, i);
+            assert_eq!(public.verify(message.as_bytes(), &signature), Ok(()));
+        }
+
+        assert_eq!(key.remaining(), 0);
+        assert!(matches!(key.sign(b"one too many"), Err(SignatureError::KeysExhausted { capacity: 4 })));
+    }
+
+    #[test]
+    fn test_forged_signatures_rejected() {
+        let mut key = MssSigningKey::generate(b"forgery", 2).unwrap();
+        let public = key.public_key();
+        let signature = key.sign(b"manifest").unwrap();
+
+        assert!(matches!(
+            public.verify(b"tampered manifest", &signature),
+            Err(SignatureError::InvalidAuthPath(ProofError::LeafMismatch { .. }))
+        ));
+
+        let other = MssSigningKey::generate(b"another signer", 2).unwrap().public_key();
+        assert!(matches!(
+            other.verify(b"manifest", &signature),
+            Err(SignatureError::InvalidAuthPath(ProofError::RootMismatch { .. }))
+        ));
+
+        let mut moved = signature.clone();
+        moved.auth_path.leaf_index = 1;
+        assert_eq!(public.verify(b"manifest", &moved), Err(SignatureError::IndexMismatch { index: 1 }));
+
+        let mut short = signature;
+        short.auth_path.proof_path.pop();
+        assert_eq!(
+            public.verify(b"manifest", &short),
+            Err(SignatureError::InvalidAuthPath(ProofError::DepthMismatch { expected: 2, actual: 1 }))
+        );
+    }
+
+    #[test]
+    fn test_complements_cannot_trade_bytes() {
+        let mut key = MssSigningKey::generate(b"malleability", 1).unwrap();
+        let public = key.public_key();
+        let signature = key.sign(b"manifest").unwrap();
+
+        // With digest bits 0 then 1 the two complements sit next to each
+        // other in the fingerprint input, so moving bytes across keeps it.
+        let digest = SHA512::hash(b"manifest");
+        let bit = (0..DIGEST_BITS - 1)
+            .find(|&i| digest_bit(&digest, i) == 0 && digest_bit(&digest, i + 1) == 1)
+            .unwrap();
+
+        let mut shifted = signature.clone();
+        shifted.complement[bit] = format!("{}{}", signature.complement[bit], &signature.complement[bit + 1][..2]);
+        shifted.complement[bit + 1] = signature.complement[bit + 1][2..].to_string();
+        assert_eq!(
+            public.verify(b"manifest", &shifted),
+            Err(SignatureError::InvalidHash(shifted.complement[bit].clone()))
+        );
+
+        let mut uppercase = signature;
+        uppercase.complement[0] = uppercase.complement[0].to_uppercase();
+        assert!(matches!(public.verify(b"manifest", &uppercase), Err(SignatureError::InvalidHash(_))));
+    }
+
+    #[test]
+    fn test_persisted_state_never_reuses_an_index() {
+        let path = state_path("state");
+        let mut key = MssSigningKey::generate(b"persisted", 2).unwrap();
+        let public = key.public_key();
+        key.save(&path).unwrap();
+
+        let first = key.sign_persisted(&path, b"first").unwrap();
+        drop(key);
+
+        // Reloading picks up after the index that was just used.
+        let mut reloaded = MssSigningKey::load(&path).unwrap();
+        assert_eq!(reloaded.next_index(), 1);
+        assert_eq!(reloaded.public_key(), public);
+
+        let second = reloaded.sign_persisted(&path, b"second").unwrap();
+        assert_eq!(first.auth_path.leaf_index, 0);
+        assert_eq!(second.auth_path.leaf_index, 1);
+        assert_eq!(public.verify(b"second", &second), Ok(()));
+
+        assert_eq!(MssSigningKey::load(&path).unwrap().next_index(), 2);
+        assert!(!temp_path(&path).exists());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_saved_state_is_private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = state_path("private");
+        // A leftover world-readable temporary file must not leak its mode.
+        std::fs::write(temp_path(&path), b"stale").unwrap();
+        std::fs::set_permissions(temp_path(&path), std::fs::Permissions::from_mode(0o644)).unwrap();
+
+        MssSigningKey::generate(b"private", 1).unwrap().save(&path).unwrap();
+
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_save_path_with_tmp_extension() {
+        let path = state_path("state").with_extension("tmp");
+        assert_ne!(temp_path(&path), path);
+
+        let mut key = MssSigningKey::generate(b"tmp extension", 1).unwrap();
+        key.sign_persisted(&path, b"first").unwrap();
+        assert_eq!(MssSigningKey::load(&path).unwrap().next_index(), 1);
+
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(matches!(
+            MssSigningKey::generate(b"too tall", MAX_HEIGHT + 1),
+            Err(SignatureError::InvalidHeight { height: 21, max: MAX_HEIGHT })
+        ));
+    }
+
+    #[test]
+    fn test_signature_serde_roundtrip() {
+        let mut key = MssSigningKey::generate(b"serde", 1).unwrap();
+        let public = key.public_key();
+        let signature = key.sign(b"manifest").unwrap();
+
+        let json = serde_json::to_string(&signature).unwrap();
+        let decoded: MssSignature = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, signature);
+        assert_eq!(public.verify(b"manifest", &decoded), Ok(()));
+
+        let public: MssPublicKey = serde_json::from_str(&serde_json::to_string(&public).unwrap()).unwrap();
+        assert_eq!(public.verify(b"manifest", &decoded), Ok(()));
+    }
+}